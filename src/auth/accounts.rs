@@ -0,0 +1,467 @@
+use crate::auth::{MicrosoftAuth, MicrosoftLogin, Yggdrasil, YggdrasilLogin};
+use crate::config::Config;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const ACCOUNTS_FILE: &str = "accounts.json";
+const KEY_FILE: &str = "accounts.key";
+const KEYRING_SERVICE: &str = "mcl";
+const KEYRING_USER: &str = "accounts";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountKind {
+    Microsoft,
+    /// Signed in to a Yggdrasil compatible server, played through
+    /// authlib-injector.
+    Yggdrasil,
+    /// Not authenticated at all, for LAN games and testing. Servers in
+    /// online mode refuse these.
+    Offline,
+}
+
+impl fmt::Display for AccountKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccountKind::Microsoft => write!(f, "microsoft"),
+            AccountKind::Yggdrasil => write!(f, "yggdrasil"),
+            AccountKind::Offline => write!(f, "offline"),
+        }
+    }
+}
+
+/// The secrets of an account, only ever written to disk encrypted.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Identifies mcl to Yggdrasil servers, tokens are bound to it.
+    #[serde(default)]
+    pub client_token: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub kind: AccountKind,
+    pub name: String,
+    pub uuid: String,
+    /// API root of the authentication server of Yggdrasil accounts.
+    pub server: Option<String>,
+    pub tokens: Tokens,
+}
+
+impl Account {
+    /// Whether `query` names this account by name or UUID.
+    pub fn matches(&self, query: &str) -> bool {
+        self.name.eq_ignore_ascii_case(query) || self.uuid == query.replace('-', "")
+    }
+
+    /// An account playing as `name` without signing in. The UUID is the one
+    /// the game and servers in offline mode derive from the name.
+    pub fn offline(name: &str) -> anyhow::Result<Self> {
+        if name.is_empty()
+            || name.len() > 16
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            bail!("player names are 1 to 16 letters, digits or underscores");
+        }
+        Ok(Self {
+            kind: AccountKind::Offline,
+            name: name.to_string(),
+            uuid: offline_uuid(name),
+            tokens: Tokens {
+                access_token: "0".to_string(),
+                refresh_token: None,
+                expires_at: None,
+                client_token: None,
+            },
+            server: None,
+        })
+    }
+
+    /// Whether the access token is about to expire.
+    pub fn needs_refresh(&self) -> bool {
+        self.tokens
+            .expires_at
+            .is_some_and(|expires_at| expires_at < Utc::now() + Duration::minutes(5))
+    }
+}
+
+/// Java's `UUID.nameUUIDFromBytes("OfflinePlayer:<name>")`: an MD5 based
+/// version 3 UUID.
+fn offline_uuid(name: &str) -> String {
+    let mut bytes = md5::compute(format!("OfflinePlayer:{}", name)).0;
+    bytes[6] = (bytes[6] & 0x0f) | 0x30;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    hex::encode(bytes)
+}
+
+impl From<MicrosoftLogin> for Account {
+    fn from(login: MicrosoftLogin) -> Self {
+        Self {
+            kind: AccountKind::Microsoft,
+            name: login.profile.name,
+            uuid: login.profile.id,
+            tokens: Tokens {
+                access_token: login.access_token,
+                refresh_token: Some(login.refresh_token),
+                expires_at: Some(login.expires_at),
+                client_token: None,
+            },
+            server: None,
+        }
+    }
+}
+
+impl From<YggdrasilLogin> for Account {
+    fn from(login: YggdrasilLogin) -> Self {
+        Self {
+            kind: AccountKind::Yggdrasil,
+            name: login.profile.name,
+            uuid: login.profile.id,
+            tokens: Tokens {
+                access_token: login.access_token,
+                refresh_token: None,
+                expires_at: None,
+                client_token: Some(login.client_token),
+            },
+            server: Some(login.server),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct StoredAccount {
+    kind: AccountKind,
+    name: String,
+    uuid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    server: Option<String>,
+    /// Hex encoded nonce followed by the encrypted `Tokens`.
+    tokens: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct StoredAccounts {
+    /// UUID of the account used when none is named.
+    default: Option<String>,
+    accounts: Vec<StoredAccount>,
+}
+
+/// The accounts mcl can launch with, in `accounts.json` next to the
+/// database. Tokens are encrypted with a key kept in the Secret Service
+/// keyring, or in a key file only the user can read where there is no
+/// keyring.
+pub struct AccountStore {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+    default: Option<String>,
+    pub accounts: Vec<Account>,
+    /// Accounts whose tokens don't decrypt with the current key, kept as
+    /// they are until removed.
+    unreadable: Vec<StoredAccount>,
+}
+
+impl AccountStore {
+    pub fn open(config: &Config) -> anyhow::Result<Self> {
+        let path = config.db_path.join(ACCOUNTS_FILE);
+        let stored: StoredAccounts = match std::fs::read(&path) {
+            Ok(buf) => serde_json::from_slice(&buf)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => StoredAccounts::default(),
+            Err(err) => return Err(err.into()),
+        };
+        let key = encryption_key(&config.db_path, !stored.accounts.is_empty())?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let mut store = Self {
+            path,
+            cipher,
+            default: stored.default,
+            accounts: Vec::new(),
+            unreadable: Vec::new(),
+        };
+        for account in stored.accounts {
+            match store.open_tokens(&account.tokens) {
+                Ok(tokens) => store.accounts.push(Account {
+                    kind: account.kind,
+                    name: account.name,
+                    uuid: account.uuid,
+                    server: account.server,
+                    tokens,
+                }),
+                Err(err) => {
+                    println!(
+                        "Skipping account {} (can't decrypt its tokens: {}), remove and add it again",
+                        &account.name, err
+                    );
+                    store.unreadable.push(account);
+                }
+            }
+        }
+        Ok(store)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let stored = StoredAccounts {
+            default: self.default.clone(),
+            accounts: self
+                .accounts
+                .iter()
+                .map(|account| {
+                    Ok(StoredAccount {
+                        kind: account.kind,
+                        name: account.name.clone(),
+                        uuid: account.uuid.clone(),
+                        server: account.server.clone(),
+                        tokens: self.seal_tokens(&account.tokens)?,
+                    })
+                })
+                .chain(self.unreadable.iter().cloned().map(Ok))
+                .collect::<anyhow::Result<_>>()?,
+        };
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&stored)?)?;
+        Ok(())
+    }
+
+    fn seal_tokens(&self, tokens: &Tokens) -> anyhow::Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let sealed = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                serde_json::to_vec(tokens)?.as_slice(),
+            )
+            .map_err(|_| anyhow!("encrypting tokens failed"))?;
+        Ok(hex::encode([nonce.as_slice(), &sealed].concat()))
+    }
+
+    fn open_tokens(&self, sealed: &str) -> anyhow::Result<Tokens> {
+        let buf = hex::decode(sealed)?;
+        if buf.len() < NONCE_LEN {
+            bail!("truncated tokens");
+        }
+        let (nonce, sealed) = buf.split_at(NONCE_LEN);
+        let plain = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| anyhow!("wrong key"))?;
+        Ok(serde_json::from_slice(&plain)?)
+    }
+
+    /// The account `query` names. A name shared by an offline and an
+    /// authenticated account is refused rather than guessed.
+    pub fn find(&self, query: &str) -> anyhow::Result<&Account> {
+        let mut found = self
+            .accounts
+            .iter()
+            .filter(|account| account.matches(query));
+        match (found.next(), found.next()) {
+            (Some(account), None) => Ok(account),
+            (Some(_), Some(_)) => bail!("several accounts are named {}, use the UUID", query),
+            (None, _) => bail!("no account named {}", query),
+        }
+    }
+
+    /// The named account, or else the default one.
+    pub fn pick(&self, query: Option<&str>) -> anyhow::Result<&Account> {
+        match query {
+            Some(query) => self.find(query),
+            None => self.default_account().ok_or_else(|| {
+                anyhow!("no account to play with, sign in with `mcl account login`")
+            }),
+        }
+    }
+
+    pub fn default_account(&self) -> Option<&Account> {
+        let default = self.default.as_deref()?;
+        self.accounts.iter().find(|account| account.uuid == default)
+    }
+
+    /// Adds an account, replacing an earlier login of the same player. The
+    /// first account becomes the default.
+    pub fn add(&mut self, account: Account) {
+        self.accounts.retain(|a| {
+            !(a.uuid == account.uuid && a.kind == account.kind && a.server == account.server)
+        });
+        if self.default_account().is_none() {
+            self.default = Some(account.uuid.clone());
+        }
+        self.accounts.push(account);
+    }
+
+    /// Adds an offline account, refusing names already taken by an
+    /// authenticated account so the two can't be confused.
+    pub fn add_offline(&mut self, name: &str) -> anyhow::Result<&Account> {
+        let account = Account::offline(name)?;
+        if let Some(other) = self
+            .accounts
+            .iter()
+            .find(|a| a.kind != AccountKind::Offline && a.name.eq_ignore_ascii_case(name))
+        {
+            bail!(
+                "{} is already signed in as a {} account",
+                &other.name,
+                other.kind
+            );
+        }
+        self.add(account);
+        Ok(self.accounts.last().unwrap())
+    }
+
+    pub fn set_default(&mut self, query: &str) -> anyhow::Result<()> {
+        self.default = Some(self.find(query)?.uuid.clone());
+        Ok(())
+    }
+
+    /// Removes the account `query` names and returns its name. Accounts
+    /// whose tokens can't be decrypted are found by name or UUID too.
+    pub fn remove(&mut self, query: &str) -> anyhow::Result<String> {
+        let uuid = match self.find(query) {
+            Ok(account) => account.uuid.clone(),
+            Err(err) => {
+                let i = self
+                    .unreadable
+                    .iter()
+                    .position(|account| {
+                        account.name.eq_ignore_ascii_case(query)
+                            || account.uuid == query.replace('-', "")
+                    })
+                    .ok_or(err)?;
+                return Ok(self.unreadable.remove(i).name);
+            }
+        };
+        let i = self
+            .accounts
+            .iter()
+            .position(|account| account.uuid == uuid)
+            .unwrap();
+        let account = self.accounts.remove(i);
+        if self.default.as_deref() == Some(account.uuid.as_str()) {
+            self.default = self.accounts.first().map(|a| a.uuid.clone());
+        }
+        Ok(account.name)
+    }
+
+    /// Gets a new access token for the named (or default) account if the
+    /// current one expired, and returns the account.
+    pub async fn refreshed(
+        &mut self,
+        config: &Config,
+        query: Option<&str>,
+    ) -> anyhow::Result<Account> {
+        let account = self.pick(query)?.clone();
+        let expired = || anyhow!("the token of {} expired, sign in again", &account.name);
+        let refreshed: Account = match account.kind {
+            AccountKind::Offline => return Ok(account),
+            AccountKind::Microsoft if !account.needs_refresh() => return Ok(account),
+            AccountKind::Microsoft => {
+                let refresh_token = account
+                    .tokens
+                    .refresh_token
+                    .as_deref()
+                    .ok_or_else(expired)?;
+                let auth = MicrosoftAuth::new(config)?;
+                let msa = auth.refresh(refresh_token).await?;
+                auth.minecraft_login(&msa).await?.into()
+            }
+            AccountKind::Yggdrasil => {
+                let server = Yggdrasil::new(account.server.as_deref().ok_or_else(expired)?);
+                let client_token = account.tokens.client_token.as_deref().ok_or_else(expired)?;
+                if server
+                    .validate(&account.tokens.access_token, client_token)
+                    .await?
+                {
+                    return Ok(account);
+                }
+                server
+                    .refresh(&account.tokens.access_token, client_token)
+                    .await?
+                    .into()
+            }
+        };
+        self.add(refreshed.clone());
+        self.save()?;
+        Ok(refreshed)
+    }
+}
+
+/// The key the tokens are encrypted with. An existing key file wins, so
+/// accounts stay readable when the keyring comes and goes. A keyring that
+/// fails while there are accounts is an error rather than a reason to
+/// start over with a new key, which would lock those accounts out.
+fn encryption_key(db_path: &Path, has_accounts: bool) -> anyhow::Result<Vec<u8>> {
+    let key_file = db_path.join(KEY_FILE);
+    if let Ok(key) = std::fs::read_to_string(&key_file) {
+        return decode_key(key.trim())
+            .map_err(|err| anyhow!("bad key in {} ({})", key_file.display(), err));
+    }
+    let keyring_failed = |err: keyring::Error| {
+        anyhow!(
+            "can't read the accounts key from the keyring ({}), unlock the keyring and try again",
+            err
+        )
+    };
+    match keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER) {
+        Ok(entry) => match entry.get_password() {
+            Ok(key) => {
+                return decode_key(&key)
+                    .map_err(|err| anyhow!("bad accounts key in the keyring ({})", err))
+            }
+            Err(keyring::Error::NoEntry) => {
+                let key = new_key();
+                if entry.set_password(&hex::encode(&key)).is_ok() {
+                    return Ok(key);
+                }
+            }
+            Err(err) if has_accounts => return Err(keyring_failed(err)),
+            Err(_) => {}
+        },
+        Err(err) if has_accounts => return Err(keyring_failed(err)),
+        Err(_) => {}
+    }
+    let key = new_key();
+    write_private(&key_file, hex::encode(&key).as_bytes())?;
+    Ok(key)
+}
+
+fn decode_key(key: &str) -> anyhow::Result<Vec<u8>> {
+    let key = hex::decode(key)?;
+    if key.len() != KEY_LEN {
+        bail!("{} bytes instead of {}", key.len(), KEY_LEN);
+    }
+    Ok(key)
+}
+
+fn new_key() -> Vec<u8> {
+    let mut key = vec![0u8; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    key
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, buf: &[u8]) -> anyhow::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(buf)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, buf: &[u8]) -> anyhow::Result<()> {
+    std::fs::write(path, buf)?;
+    Ok(())
+}
@@ -0,0 +1,311 @@
+use crate::auth::{Cape, MinecraftServices, Skin};
+use crate::config::Config;
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const MSA_SCOPE: &str = "XboxLive.signin offline_access";
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// What the user has to do to let mcl sign in.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: i64,
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    pub message: Option<String>,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MsaToken {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum TokenResponse {
+    Token(MsaToken),
+    Error {
+        error: String,
+        error_description: Option<String>,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+struct XboxDisplayClaims {
+    xui: Vec<XboxUserInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct XboxUserInfo {
+    uhs: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct XboxToken {
+    token: String,
+    display_claims: XboxDisplayClaims,
+}
+
+#[derive(Deserialize, Debug)]
+struct XboxError {
+    #[serde(rename = "XErr")]
+    xerr: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct MinecraftToken {
+    access_token: String,
+    expires_in: i64,
+}
+
+#[derive(Deserialize, Debug)]
+struct Entitlements {
+    #[serde(default)]
+    items: Vec<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MinecraftProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub skins: Vec<Skin>,
+    #[serde(default)]
+    pub capes: Vec<Cape>,
+}
+
+/// The result of signing in: the Minecraft profile, a token to play with and
+/// the Microsoft refresh token to get the next one.
+#[derive(Debug, Clone)]
+pub struct MicrosoftLogin {
+    pub profile: MinecraftProfile,
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+    pub refresh_token: String,
+}
+
+/// The Microsoft account sign in chain: Microsoft OAuth, Xbox Live, XSTS and
+/// finally Minecraft services.
+pub struct MicrosoftAuth {
+    client: reqwest::Client,
+    client_id: String,
+    msa_url: String,
+    xbox_user_url: String,
+    xsts_url: String,
+    services_url: String,
+}
+
+impl MicrosoftAuth {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let client_id = config.msa_client_id.clone().ok_or_else(|| {
+            anyhow!("msa_client_id is not set, add the id of an Azure application to the config")
+        })?;
+        let base = |url: &str| url.trim_end_matches('/').to_string();
+        Ok(Self {
+            client: reqwest::Client::new(),
+            client_id,
+            msa_url: base(&config.msa_auth_url),
+            xbox_user_url: base(&config.xbox_user_auth_url),
+            xsts_url: base(&config.xbox_xsts_url),
+            services_url: base(&config.minecraft_services_url),
+        })
+    }
+
+    pub async fn device_code(&self) -> anyhow::Result<DeviceCode> {
+        let res = self
+            .client
+            .post(format!("{}/devicecode", self.msa_url))
+            .form(&[("client_id", self.client_id.as_str()), ("scope", MSA_SCOPE)])
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json().await?)
+    }
+
+    /// Polls until the user has entered the code, declined or let it
+    /// expire.
+    pub async fn poll_token(&self, code: &DeviceCode) -> anyhow::Result<MsaToken> {
+        let mut interval = code.interval;
+        let deadline = Utc::now() + Duration::seconds(code.expires_in);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+            let res = self
+                .token(&[
+                    ("grant_type", DEVICE_CODE_GRANT),
+                    ("device_code", &code.device_code),
+                ])
+                .await?;
+            match res {
+                TokenResponse::Token(token) => return Ok(token),
+                TokenResponse::Error { error, .. } if error == "authorization_pending" => {}
+                TokenResponse::Error { error, .. } if error == "slow_down" => interval += 5,
+                TokenResponse::Error { error, .. } if error == "authorization_declined" => {
+                    bail!("sign in was declined")
+                }
+                TokenResponse::Error { error, .. } if error == "expired_token" => {
+                    bail!("the code expired before sign in finished")
+                }
+                TokenResponse::Error {
+                    error,
+                    error_description,
+                } => bail!(
+                    "sign in failed: {} ({})",
+                    error,
+                    error_description.unwrap_or_default()
+                ),
+            }
+            if Utc::now() > deadline {
+                bail!("the code expired before sign in finished");
+            }
+        }
+    }
+
+    /// Trades a refresh token for a new Microsoft token.
+    pub async fn refresh(&self, refresh_token: &str) -> anyhow::Result<MsaToken> {
+        match self
+            .token(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("scope", MSA_SCOPE),
+            ])
+            .await?
+        {
+            TokenResponse::Token(token) => Ok(token),
+            TokenResponse::Error {
+                error,
+                error_description,
+            } => bail!(
+                "refreshing the Microsoft token failed: {} ({})",
+                error,
+                error_description.unwrap_or_default()
+            ),
+        }
+    }
+
+    /// The token endpoint answers 400 with an error object while polling, so
+    /// the status isn't checked here.
+    async fn token(&self, params: &[(&str, &str)]) -> anyhow::Result<TokenResponse> {
+        let mut form = vec![("client_id", self.client_id.as_str())];
+        form.extend_from_slice(params);
+        let res = self
+            .client
+            .post(format!("{}/token", self.msa_url))
+            .form(&form)
+            .send()
+            .await?;
+        Ok(res.json().await?)
+    }
+
+    async fn xbox_live(&self, msa_token: &str) -> anyhow::Result<XboxToken> {
+        let res = self
+            .client
+            .post(format!("{}/user/authenticate", self.xbox_user_url))
+            .json(&json!({
+                "Properties": {
+                    "AuthMethod": "RPS",
+                    "SiteName": "user.auth.xboxlive.com",
+                    "RpsTicket": format!("d={}", msa_token),
+                },
+                "RelyingParty": "http://auth.xboxlive.com",
+                "TokenType": "JWT",
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json().await?)
+    }
+
+    async fn xsts(&self, xbox_token: &str) -> anyhow::Result<XboxToken> {
+        let res = self
+            .client
+            .post(format!("{}/xsts/authorize", self.xsts_url))
+            .json(&json!({
+                "Properties": {
+                    "SandboxId": "RETAIL",
+                    "UserTokens": [xbox_token],
+                },
+                "RelyingParty": "rp://api.minecraftservices.com/",
+                "TokenType": "JWT",
+            }))
+            .send()
+            .await?;
+        if res.status() == reqwest::StatusCode::UNAUTHORIZED {
+            let err: XboxError = res.json().await?;
+            bail!(match err.xerr {
+                2148916233 =>
+                    "this Microsoft account has no Xbox account, sign in on xbox.com first"
+                        .to_string(),
+                2148916235 => "Xbox Live is not available in this account's country".to_string(),
+                2148916236 | 2148916237 =>
+                    "this account needs adult verification on xbox.com".to_string(),
+                2148916238 => "this is a child account, it has to be added to a family by an adult"
+                    .to_string(),
+                code => format!("Xbox Live refused the sign in (XErr {})", code),
+            });
+        }
+        Ok(res.error_for_status()?.json().await?)
+    }
+
+    async fn login_with_xbox(&self, xsts: &XboxToken) -> anyhow::Result<MinecraftToken> {
+        let uhs = &xsts
+            .display_claims
+            .xui
+            .first()
+            .ok_or_else(|| anyhow!("XSTS token without user hash"))?
+            .uhs;
+        let res = self
+            .client
+            .post(format!(
+                "{}/authentication/login_with_xbox",
+                self.services_url
+            ))
+            .json(&json!({
+                "identityToken": format!("XBL3.0 x={};{}", uhs, &xsts.token),
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json().await?)
+    }
+
+    async fn owns_minecraft(&self, mc_token: &str) -> anyhow::Result<bool> {
+        let res = self
+            .client
+            .get(format!("{}/entitlements/mcstore", self.services_url))
+            .bearer_auth(mc_token)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(!res.json::<Entitlements>().await?.items.is_empty())
+    }
+
+    /// Runs the rest of the chain once a Microsoft token is there.
+    pub async fn minecraft_login(&self, msa: &MsaToken) -> anyhow::Result<MicrosoftLogin> {
+        let xbox = self.xbox_live(&msa.access_token).await?;
+        let xsts = self.xsts(&xbox.token).await?;
+        let mc = self.login_with_xbox(&xsts).await?;
+        if !self.owns_minecraft(&mc.access_token).await? {
+            bail!("this account doesn't own Minecraft");
+        }
+        let profile = MinecraftServices::new(&self.services_url, &mc.access_token)
+            .profile()
+            .await?;
+        Ok(MicrosoftLogin {
+            profile,
+            expires_at: Utc::now() + Duration::seconds(mc.expires_in),
+            access_token: mc.access_token,
+            refresh_token: msa.refresh_token.clone(),
+        })
+    }
+}
@@ -0,0 +1,9 @@
+mod accounts;
+mod microsoft;
+mod skins;
+mod yggdrasil;
+
+pub use accounts::*;
+pub use microsoft::*;
+pub use skins::*;
+pub use yggdrasil::*;
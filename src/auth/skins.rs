@@ -0,0 +1,163 @@
+use crate::auth::MinecraftProfile;
+use anyhow::{anyhow, bail};
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::str::FromStr;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Skin {
+    pub id: String,
+    pub state: String,
+    pub url: String,
+    pub variant: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cape {
+    pub id: String,
+    pub state: String,
+    pub url: String,
+    pub alias: Option<String>,
+}
+
+impl Cape {
+    pub fn is_active(&self) -> bool {
+        self.state == "ACTIVE"
+    }
+}
+
+/// The arm width a skin is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkinModel {
+    Classic,
+    Slim,
+}
+
+impl SkinModel {
+    fn variant(&self) -> &'static str {
+        match self {
+            SkinModel::Classic => "classic",
+            SkinModel::Slim => "slim",
+        }
+    }
+}
+
+impl FromStr for SkinModel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" => Ok(SkinModel::Classic),
+            "slim" => Ok(SkinModel::Slim),
+            _ => bail!("unknown skin model {} (expected classic or slim)", s),
+        }
+    }
+}
+
+/// Checks that `buf` is a PNG the game accepts as a skin: 64x64, or 64x32
+/// for the old format.
+pub fn check_skin(buf: &[u8]) -> anyhow::Result<()> {
+    // the IHDR chunk always comes first, right after the signature
+    if buf.len() < 24 || buf[..8] != PNG_SIGNATURE || &buf[12..16] != b"IHDR" {
+        bail!("skins have to be PNG images");
+    }
+    let width = u32::from_be_bytes(buf[16..20].try_into()?);
+    let height = u32::from_be_bytes(buf[20..24].try_into()?);
+    if (width, height) != (64, 64) && (width, height) != (64, 32) {
+        bail!("skins are 64x64 or 64x32 pixels, not {}x{}", width, height);
+    }
+    Ok(())
+}
+
+/// The profile endpoints of Minecraft services, for the player `token`
+/// belongs to.
+pub struct MinecraftServices {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl MinecraftServices {
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> anyhow::Result<reqwest::Response> {
+        Ok(req
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    pub async fn profile(&self) -> anyhow::Result<MinecraftProfile> {
+        let res = self
+            .client
+            .get(format!("{}/minecraft/profile", self.base_url))
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        if res.status() == reqwest::StatusCode::NOT_FOUND {
+            bail!("this account has no Minecraft profile yet, pick a name on minecraft.net first");
+        }
+        Ok(res.error_for_status()?.json().await?)
+    }
+
+    pub async fn upload_skin(&self, png: Vec<u8>, model: SkinModel) -> anyhow::Result<()> {
+        check_skin(&png)?;
+        let form = Form::new().text("variant", model.variant()).part(
+            "file",
+            Part::bytes(png)
+                .file_name("skin.png")
+                .mime_str("image/png")?,
+        );
+        self.send(
+            self.client
+                .post(format!("{}/minecraft/profile/skins", self.base_url))
+                .multipart(form),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Goes back to the default skin.
+    pub async fn reset_skin(&self) -> anyhow::Result<()> {
+        self.send(
+            self.client
+                .delete(format!("{}/minecraft/profile/skins/active", self.base_url)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Shows the cape named by id or alias, which the player has to own.
+    pub async fn set_cape(&self, query: &str) -> anyhow::Result<Cape> {
+        let cape = self
+            .profile()
+            .await?
+            .capes
+            .into_iter()
+            .find(|cape| {
+                cape.id == query
+                    || cape
+                        .alias
+                        .as_deref()
+                        .is_some_and(|alias| alias.eq_ignore_ascii_case(query))
+            })
+            .ok_or_else(|| anyhow!("this account has no cape {}", query))?;
+        self.send(
+            self.client
+                .put(format!("{}/minecraft/profile/capes/active", self.base_url))
+                .json(&json!({ "capeId": &cape.id })),
+        )
+        .await?;
+        Ok(cape)
+    }
+}
@@ -0,0 +1,254 @@
+use crate::checksum::sha256_hex;
+use crate::config::Config;
+use anyhow::{anyhow, bail};
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+
+const AUTHLIB_INJECTOR_JAR: &str = "authlib-injector.jar";
+/// Header through which a server's web page points at its API root.
+const API_LOCATION_HEADER: &str = "X-Authlib-Injector-API-Location";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct YggdrasilProfile {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AuthResponse {
+    access_token: String,
+    client_token: String,
+    #[serde(default)]
+    available_profiles: Vec<YggdrasilProfile>,
+    selected_profile: Option<YggdrasilProfile>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct YggdrasilError {
+    error_message: Option<String>,
+    error: Option<String>,
+}
+
+/// A signed in Yggdrasil session.
+#[derive(Debug, Clone)]
+pub struct YggdrasilLogin {
+    pub server: String,
+    pub profile: YggdrasilProfile,
+    pub access_token: String,
+    pub client_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct AuthlibInjectorArtifact {
+    version: String,
+    download_url: String,
+    checksums: AuthlibInjectorChecksums,
+}
+
+#[derive(Deserialize, Debug)]
+struct AuthlibInjectorChecksums {
+    sha256: String,
+}
+
+/// An authentication server speaking the Yggdrasil protocol, as supported
+/// by authlib-injector.
+pub struct Yggdrasil {
+    client: reqwest::Client,
+    /// The API root, e.g. `https://example.com/api/yggdrasil`.
+    pub root: String,
+}
+
+impl Yggdrasil {
+    pub fn new(root: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            root: root.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Finds the API root behind a URL the user gave, following the API
+    /// location indication of authlib-injector.
+    pub async fn discover(url: &str) -> anyhow::Result<Self> {
+        let url = if url.contains("://") {
+            url.to_string()
+        } else {
+            format!("https://{}", url)
+        };
+        let res = reqwest::get(&url).await?;
+        let root = match res.headers().get(API_LOCATION_HEADER) {
+            Some(location) => res.url().join(location.to_str()?)?.to_string(),
+            None => url,
+        };
+        Ok(Self::new(&root))
+    }
+
+    async fn post(
+        &self,
+        endpoint: &str,
+        body: serde_json::Value,
+    ) -> anyhow::Result<reqwest::Response> {
+        let res = self
+            .client
+            .post(format!("{}/authserver/{}", self.root, endpoint))
+            .json(&body)
+            .send()
+            .await?;
+        if res.status().is_client_error() {
+            let err: YggdrasilError = res.json().await?;
+            bail!(
+                "{}",
+                err.error_message
+                    .or(err.error)
+                    .unwrap_or_else(|| "the authentication server refused the request".to_string())
+            );
+        }
+        Ok(res.error_for_status()?)
+    }
+
+    pub async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> anyhow::Result<YggdrasilLogin> {
+        let mut client_token = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut client_token);
+        let res: AuthResponse = self
+            .post(
+                "authenticate",
+                json!({
+                    "agent": { "name": "Minecraft", "version": 1 },
+                    "username": username,
+                    "password": password,
+                    "clientToken": hex::encode(client_token),
+                    "requestUser": false,
+                }),
+            )
+            .await?
+            .json()
+            .await?;
+        let profile = match res.selected_profile {
+            Some(profile) => profile,
+            None => match res.available_profiles.as_slice() {
+                [profile] => profile.clone(),
+                [] => bail!("{} has no player profiles on this server", username),
+                profiles => bail!(
+                    "{} has several players ({}), which isn't supported yet",
+                    username,
+                    profiles
+                        .iter()
+                        .map(|p| p.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            },
+        };
+        Ok(YggdrasilLogin {
+            server: self.root.clone(),
+            profile,
+            access_token: res.access_token,
+            client_token: res.client_token,
+        })
+    }
+
+    /// Whether the server still accepts a token.
+    pub async fn validate(&self, access_token: &str, client_token: &str) -> anyhow::Result<bool> {
+        let res = self
+            .client
+            .post(format!("{}/authserver/validate", self.root))
+            .json(&json!({ "accessToken": access_token, "clientToken": client_token }))
+            .send()
+            .await?;
+        Ok(res.status().is_success())
+    }
+
+    pub async fn refresh(
+        &self,
+        access_token: &str,
+        client_token: &str,
+    ) -> anyhow::Result<YggdrasilLogin> {
+        let res: AuthResponse = self
+            .post(
+                "refresh",
+                json!({ "accessToken": access_token, "clientToken": client_token }),
+            )
+            .await?
+            .json()
+            .await?;
+        Ok(YggdrasilLogin {
+            server: self.root.clone(),
+            profile: res
+                .selected_profile
+                .ok_or_else(|| anyhow!("the server refreshed the token without a player"))?,
+            access_token: res.access_token,
+            client_token: res.client_token,
+        })
+    }
+
+    /// The JVM arguments loading authlib-injector against this server, with
+    /// the server metadata prefetched so the game doesn't have to ask for it.
+    pub async fn jvm_args(&self, config: &Config) -> anyhow::Result<Vec<String>> {
+        let jar = ensure_authlib_injector(config).await?;
+        let metadata = self
+            .client
+            .get(&self.root)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(vec![
+            format!("-javaagent:{}={}", jar.display(), &self.root),
+            format!(
+                "-Dauthlibinjector.yggdrasil.prefetched={}",
+                base64::engine::general_purpose::STANDARD.encode(&metadata)
+            ),
+        ])
+    }
+}
+
+/// Downloads the latest authlib-injector unless it's already there. A jar
+/// downloaded earlier is used as is when the latest version can't be
+/// looked up, e.g. offline.
+async fn ensure_authlib_injector(config: &Config) -> anyhow::Result<PathBuf> {
+    let jar = config.db_path.join(AUTHLIB_INJECTOR_JAR);
+    let artifact = match latest_authlib_injector(config).await {
+        Ok(artifact) => artifact,
+        Err(err) if jar.is_file() => {
+            println!(
+                "Could not look for a newer authlib-injector ({}), using the one there",
+                err
+            );
+            return Ok(jar);
+        }
+        Err(err) => return Err(err),
+    };
+    if let Ok(existing) = std::fs::read(&jar) {
+        if sha256_hex(&existing) == artifact.checksums.sha256 {
+            return Ok(jar);
+        }
+    }
+    println!("=> authlib-injector {}", &artifact.version);
+    let buf = reqwest::get(&artifact.download_url)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    if sha256_hex(&buf) != artifact.checksums.sha256 {
+        bail!("checksum verification failed for authlib-injector");
+    }
+    std::fs::write(&jar, &buf)?;
+    Ok(jar)
+}
+
+async fn latest_authlib_injector(config: &Config) -> anyhow::Result<AuthlibInjectorArtifact> {
+    Ok(reqwest::get(&config.authlib_injector_url)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
@@ -1,7 +1,7 @@
+use crate::minecraft::VersionManifest;
+use crate::Config;
 use std::fs::File;
 use std::io::Write;
-use crate::minecraft::{VersionManifest};
-use crate::Config;
 use std::time::{Duration, SystemTime};
 
 pub async fn read_manifest(config: &Config) -> anyhow::Result<VersionManifest> {
@@ -10,7 +10,8 @@ pub async fn read_manifest(config: &Config) -> anyhow::Result<VersionManifest> {
         Ok(mut f) => {
             let md = f.metadata()?;
             let expiry_secs = u64::from(&config.cache_expiry_days * 86400);
-            if SystemTime::now().duration_since(md.modified()?)? > Duration::from_secs(expiry_secs) {
+            if SystemTime::now().duration_since(md.modified()?)? > Duration::from_secs(expiry_secs)
+            {
                 println!(
                     "Local version manifest older than {} days, refreshing...",
                     &config.cache_expiry_days
@@ -21,9 +22,12 @@ pub async fn read_manifest(config: &Config) -> anyhow::Result<VersionManifest> {
             } else {
                 serde_json::from_slice(std::fs::read(&manifest_path)?.as_slice())?
             }
-        },
+        }
         Err(err) => {
-            println!("Error reading local version manifest ({}). Fetching...", err);
+            println!(
+                "Error reading local version manifest ({}). Fetching...",
+                err
+            );
             let m = VersionManifest::fetch_async().await?;
             write_manifest(config, &m)?;
             m
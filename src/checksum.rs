@@ -1,3 +1,6 @@
+use anyhow::ensure;
+use sha1::Digest;
+use std::collections::HashMap;
 use std::fmt;
 
 pub trait Checksum {
@@ -12,3 +15,38 @@ impl fmt::Display for ChecksumVerificationError {
         write!(f, "checksum verification failed")
     }
 }
+
+pub fn sha1_hex(buf: &[u8]) -> String {
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(buf);
+    hex::encode(hasher.finalize())
+}
+
+pub fn sha256_hex(buf: &[u8]) -> String {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(buf);
+    hex::encode(hasher.finalize())
+}
+
+pub fn sha512_hex(buf: &[u8]) -> String {
+    let mut hasher = sha2::Sha512::new();
+    hasher.update(buf);
+    hex::encode(hasher.finalize())
+}
+
+/// Checks `buf` against a Modrinth-style `hashes` map, verifying whichever
+/// of sha512 and sha1 are present. A map with neither is refused rather
+/// than taken as verified.
+pub fn verify_hashes(hashes: &HashMap<String, String>, buf: &[u8]) -> anyhow::Result<()> {
+    ensure!(
+        hashes.contains_key("sha512") || hashes.contains_key("sha1"),
+        "no sha1 or sha512 hash to verify the download against"
+    );
+    if let Some(sha512) = hashes.get("sha512") {
+        ensure!(&sha512_hex(buf) == sha512, ChecksumVerificationError);
+    }
+    if let Some(sha1) = hashes.get("sha1") {
+        ensure!(&sha1_hex(buf) == sha1, ChecksumVerificationError);
+    }
+    Ok(())
+}
@@ -1,10 +1,42 @@
+use crate::db::LaunchSettings;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[derive(Debug, Default)]
+const CONFIG_ENV: &str = "MCL_CONFIG";
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
 pub struct Config {
     pub db_path: PathBuf,
     pub cache_path: PathBuf,
     pub cache_expiry_days: u32,
+    pub fabric_meta_url: String,
+    pub quilt_meta_url: String,
+    pub forge_maven_url: String,
+    pub forge_promotions_url: String,
+    pub neoforge_maven_url: String,
+    pub neoforge_api_url: String,
+    pub java_path: String,
+    pub java_runtime_manifest_url: String,
+    pub modrinth_api_url: String,
+    pub curseforge_api_url: String,
+    pub curseforge_api_key: Option<String>,
+    /// Id of the Azure application Microsoft sign in goes through.
+    pub msa_client_id: Option<String>,
+    pub msa_auth_url: String,
+    pub xbox_user_auth_url: String,
+    pub xbox_xsts_url: String,
+    pub minecraft_services_url: String,
+    /// Release metadata of authlib-injector, for Yggdrasil accounts.
+    pub authlib_injector_url: String,
+    /// Launch settings of installations that don't set their own.
+    pub launch: LaunchSettings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Config {
@@ -14,11 +46,63 @@ impl Config {
             db_path,
             cache_path: dirs::cache_dir().unwrap().join("mc-installer"),
             cache_expiry_days: 5,
+            fabric_meta_url: "https://meta.fabricmc.net".to_string(),
+            quilt_meta_url: "https://meta.quiltmc.org".to_string(),
+            forge_maven_url: "https://maven.minecraftforge.net".to_string(),
+            forge_promotions_url:
+                "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json"
+                    .to_string(),
+            neoforge_maven_url: "https://maven.neoforged.net/releases".to_string(),
+            neoforge_api_url: "https://maven.neoforged.net/api/maven".to_string(),
+            java_path: "java".to_string(),
+            java_runtime_manifest_url: "https://launchermeta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json".to_string(),
+            modrinth_api_url: "https://api.modrinth.com".to_string(),
+            curseforge_api_url: "https://api.curseforge.com".to_string(),
+            curseforge_api_key: None,
+            msa_client_id: None,
+            msa_auth_url: "https://login.microsoftonline.com/consumers/oauth2/v2.0".to_string(),
+            xbox_user_auth_url: "https://user.auth.xboxlive.com".to_string(),
+            xbox_xsts_url: "https://xsts.auth.xboxlive.com".to_string(),
+            minecraft_services_url: "https://api.minecraftservices.com".to_string(),
+            authlib_injector_url: "https://authlib-injector.yushi.moe/artifact/latest.json"
+                .to_string(),
+            launch: LaunchSettings {
+                max_memory: Some(2048),
+                ..LaunchSettings::default()
+            },
         }
     }
 
+    /// Reads the config file named by `MCL_CONFIG`, or `config.json` in the
+    /// user's config directory. Missing keys (or a missing file) fall back to
+    /// the defaults from `Config::new`.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = match std::env::var_os(CONFIG_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => dirs::config_dir()
+                .unwrap()
+                .join("mc-installer")
+                .join("config.json"),
+        };
+        match std::fs::read(&path) {
+            Ok(buf) => Ok(serde_json::from_slice(&buf)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn db_file(&self) -> PathBuf {
+        self.db_path.join("db.json")
+    }
+
+    /// Java runtimes shared by every installation.
+    pub fn runtimes_dir(&self) -> PathBuf {
+        self.db_path.join("runtimes")
+    }
+
     pub fn prepare_dirs(&self) -> anyhow::Result<()> {
-        let dirs = vec![&self.db_path, &self.cache_path];
+        let runtimes_dir = self.runtimes_dir();
+        let dirs = vec![&self.db_path, &self.cache_path, &runtimes_dir];
         for dir in dirs.into_iter() {
             std::fs::create_dir_all(dir)?;
         }
@@ -1,14 +1,16 @@
+use crate::loader::LoaderKind;
 use crate::minecraft;
 use chrono::serde::{ts_seconds, ts_seconds_option};
 use chrono::{DateTime, Utc};
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Installation {
     pub name: String,
     pub version: String,
@@ -18,6 +20,230 @@ pub struct Installation {
     pub updated_at: Option<DateTime<Utc>>,
     pub path: PathBuf,
     pub lib_dir: PathBuf,
+    #[serde(default)]
+    pub loader: Option<LoaderKind>,
+    #[serde(default)]
+    pub loader_version: Option<String>,
+    /// Game directory outside of the installation, e.g. one shared with the
+    /// official launcher.
+    #[serde(default)]
+    pub custom_game_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub settings: LaunchSettings,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// How the game of an installation is started. Unset fields fall back to
+/// the defaults in the config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LaunchSettings {
+    #[serde(default)]
+    pub java_path: Option<PathBuf>,
+    /// Initial heap, in MiB.
+    #[serde(default)]
+    pub min_memory: Option<u32>,
+    /// Maximum heap, in MiB.
+    #[serde(default)]
+    pub max_memory: Option<u32>,
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Command the game is run through, e.g. `gamemoderun` or `prime-run`.
+    #[serde(default)]
+    pub wrapper: Vec<String>,
+    #[serde(default)]
+    pub resolution: Option<Resolution>,
+}
+
+/// Keys accepted by `LaunchSettings::set`.
+pub const LAUNCH_SETTING_KEYS: [&str; 7] = [
+    "java",
+    "min-memory",
+    "max-memory",
+    "jvm-args",
+    "env",
+    "wrapper",
+    "resolution",
+];
+
+impl LaunchSettings {
+    /// These settings, with anything unset taken from `defaults`. Environment
+    /// variables are merged.
+    pub fn or(&self, defaults: &LaunchSettings) -> LaunchSettings {
+        let pick = |mine: &Vec<String>, theirs: &Vec<String>| {
+            if mine.is_empty() {
+                theirs.clone()
+            } else {
+                mine.clone()
+            }
+        };
+        let mut env = defaults.env.clone();
+        env.extend(self.env.clone());
+        LaunchSettings {
+            java_path: self
+                .java_path
+                .clone()
+                .or_else(|| defaults.java_path.clone()),
+            min_memory: self.min_memory.or(defaults.min_memory),
+            max_memory: self.max_memory.or(defaults.max_memory),
+            jvm_args: pick(&self.jvm_args, &defaults.jvm_args),
+            env,
+            wrapper: pick(&self.wrapper, &defaults.wrapper),
+            resolution: self
+                .resolution
+                .clone()
+                .or_else(|| defaults.resolution.clone()),
+        }
+    }
+
+    /// Sets one of `LAUNCH_SETTING_KEYS` from its command line form, or
+    /// unsets it when `values` is empty. `env` takes `NAME=value` and unsets
+    /// with `NAME=`. `jvm-args` and `wrapper` take several values as they
+    /// are, or a single one split like a shell would, so quoted parts can
+    /// hold spaces.
+    pub fn set(&mut self, key: &str, values: &[String]) -> anyhow::Result<()> {
+        if matches!(key, "jvm-args" | "wrapper") {
+            let words = match values {
+                [line] => split_args(line)?,
+                _ => values.to_vec(),
+            };
+            match key {
+                "jvm-args" => self.jvm_args = words,
+                _ => self.wrapper = words,
+            }
+            return Ok(());
+        }
+        let value = match values {
+            [] => None,
+            [value] => Some(value.as_str()),
+            _ => anyhow::bail!("{} takes a single value", key),
+        };
+        match key {
+            "java" => self.java_path = value.map(PathBuf::from),
+            "min-memory" => {
+                let min = value.map(parse_memory).transpose()?;
+                check_memory(min, self.max_memory)?;
+                self.min_memory = min;
+            }
+            "max-memory" => {
+                let max = value.map(parse_memory).transpose()?;
+                check_memory(self.min_memory, max)?;
+                self.max_memory = max;
+            }
+            "env" => match value.and_then(|v| v.split_once('=')) {
+                Some((name, "")) => {
+                    self.env.remove(name);
+                }
+                Some((name, v)) => {
+                    self.env.insert(name.to_string(), v.to_string());
+                }
+                None if value.is_none() => self.env.clear(),
+                None => anyhow::bail!("env takes NAME=value"),
+            },
+            "resolution" => {
+                self.resolution = match value {
+                    Some(v) => {
+                        let (width, height) = v
+                            .split_once('x')
+                            .ok_or_else(|| anyhow::anyhow!("resolution takes WIDTHxHEIGHT"))?;
+                        Some(Resolution {
+                            width: width.parse()?,
+                            height: height.parse()?,
+                        })
+                    }
+                    None => None,
+                }
+            }
+            _ => anyhow::bail!(
+                "unknown setting {} (expected one of {})",
+                key,
+                LAUNCH_SETTING_KEYS.join(", ")
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// The JVM refuses to start with an initial heap above the maximum.
+fn check_memory(min: Option<u32>, max: Option<u32>) -> anyhow::Result<()> {
+    if let (Some(min), Some(max)) = (min, max) {
+        anyhow::ensure!(
+            min <= max,
+            "min-memory ({}M) can't be above max-memory ({}M)",
+            min,
+            max
+        );
+    }
+    Ok(())
+}
+
+/// Splits a command line into arguments the way a POSIX shell does with
+/// whitespace, single and double quotes and backslashes, without expanding
+/// anything.
+fn split_args(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => args.extend(arg.take()),
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => anyhow::bail!("unterminated ' in {}", line),
+                    }
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => arg.push(c),
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => anyhow::bail!("unterminated \" in {}", line),
+                        },
+                        Some(c) => arg.push(c),
+                        None => anyhow::bail!("unterminated \" in {}", line),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars.next().unwrap_or('\\');
+                arg.get_or_insert_with(String::new).push(escaped);
+            }
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+/// Reads a heap size in MiB, accepting `M` and `G` suffixes like `-Xmx`.
+fn parse_memory(value: &str) -> anyhow::Result<u32> {
+    let value = value.trim();
+    let (digits, factor) = match value.chars().last() {
+        Some('g' | 'G') => (&value[..value.len() - 1], 1024),
+        Some('m' | 'M') => (&value[..value.len() - 1], 1),
+        _ => (value, 1),
+    };
+    digits
+        .parse::<u32>()?
+        .checked_mul(factor)
+        .ok_or_else(|| anyhow::anyhow!("{} is too much memory", value))
 }
 
 impl Installation {
@@ -38,6 +264,43 @@ impl Installation {
             version: version.id.clone(),
             created_at: chrono::DateTime::from(SystemTime::now()),
             updated_at: None,
+            loader: None,
+            loader_version: None,
+            custom_game_dir: None,
+            settings: LaunchSettings::default(),
+        }
+    }
+
+    /// Loads the installed version JSON, resolving any `inheritsFrom` parents
+    /// from the JSONs stored alongside it.
+    pub fn load_version(&self) -> anyhow::Result<minecraft::Version> {
+        let load = |id: &str| -> anyhow::Result<minecraft::RawVersion> {
+            let buf = std::fs::read(self.path.join(format!("{}.json", id)))?;
+            Ok(serde_json::from_slice(&buf)?)
+        };
+        load(&self.version)?.resolve(load)
+    }
+
+    /// The vanilla version at the root of the installed version's
+    /// `inheritsFrom` chain.
+    pub fn game_version(&self) -> anyhow::Result<String> {
+        let mut id = self.version.clone();
+        for _ in 0..16 {
+            let buf = std::fs::read(self.path.join(format!("{}.json", id)))?;
+            let raw: minecraft::RawVersion = serde_json::from_slice(&buf)?;
+            match raw.inherits_from {
+                Some(parent) => id = parent,
+                None => return Ok(id),
+            }
+        }
+        anyhow::bail!("version {} inherits too deeply", self.version)
+    }
+
+    /// The `.minecraft` directory the game runs in.
+    pub fn game_dir(&self) -> PathBuf {
+        match &self.custom_game_dir {
+            Some(dir) => dir.clone(),
+            None => self.path.join(".minecraft"),
         }
     }
 
@@ -62,12 +325,26 @@ impl Db {
             installations: Vec::new(),
         }
     }
+
+    pub fn installation(&self, name: &str) -> anyhow::Result<&Installation> {
+        self.installations
+            .iter()
+            .find(|inst| inst.name == name)
+            .ok_or_else(|| anyhow::anyhow!("no installation named {}", name))
+    }
+
+    pub fn installation_mut(&mut self, name: &str) -> anyhow::Result<&mut Installation> {
+        self.installations
+            .iter_mut()
+            .find(|inst| inst.name == name)
+            .ok_or_else(|| anyhow::anyhow!("no installation named {}", name))
+    }
 }
 
 #[allow(unused)]
 pub struct JsonFileDb {
     pub db: Db,
-    handle: File,
+    path: PathBuf,
 }
 
 #[allow(unused)]
@@ -77,26 +354,96 @@ impl JsonFileDb {
             Ok(mut handle) => {
                 let mut buf = String::new();
                 handle.read_to_string(&mut buf)?;
-                let db = serde_json::from_str(&buf)?;
-                Ok(Self { handle, db })
+                let db = if buf.trim().is_empty() {
+                    Db::new()
+                } else {
+                    serde_json::from_str(&buf)?
+                };
+                Ok(Self {
+                    db,
+                    path: path.to_path_buf(),
+                })
             }
             Err(err) => {
                 println!(
                     "Error opening database at {:?} ({}), creating instead...",
                     path, err
                 );
-                let mut handle = File::create(path)?;
+                File::create(path)?;
                 Ok(Self {
-                    handle,
                     db: Db::new(),
+                    path: path.to_path_buf(),
                 })
             }
         }
     }
 
-   pub fn commit(&mut self) -> anyhow::Result<()> {
+    pub fn commit(&mut self) -> anyhow::Result<()> {
         let buf = serde_json::to_vec(&self.db)?;
-        self.handle.write_all(&buf)?;
+        let mut handle = File::create(&self.path)?;
+        handle.write_all(&buf)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn split_args_like_a_shell() {
+        assert_eq!(
+            split_args(r#"-Xss1M  -Dfoo="a b" '-Dbar=c d' e\ f "" x"y"z"#).unwrap(),
+            ["-Xss1M", "-Dfoo=a b", "-Dbar=c d", "e f", "", "xyz"]
+        );
+        assert_eq!(
+            split_args(r#""say \"hi\" \n""#).unwrap(),
+            [r#"say "hi" \n"#]
+        );
+        assert!(split_args("\"open").is_err());
+        assert!(split_args("'open").is_err());
+    }
+
+    #[test]
+    fn args_with_spaces() {
+        let mut settings = LaunchSettings::default();
+        settings
+            .set("jvm-args", &strings(&["-Dfoo=\"a b\" -Xss1M"]))
+            .unwrap();
+        assert_eq!(settings.jvm_args, ["-Dfoo=a b", "-Xss1M"]);
+        settings
+            .set("jvm-args", &strings(&["-Dbar=c d", "-Xss1M"]))
+            .unwrap();
+        assert_eq!(settings.jvm_args, ["-Dbar=c d", "-Xss1M"]);
+        settings
+            .set("wrapper", &strings(&["'/opt/my tools/run.sh' --fast"]))
+            .unwrap();
+        assert_eq!(settings.wrapper, ["/opt/my tools/run.sh", "--fast"]);
+        settings.set("wrapper", &[]).unwrap();
+        assert!(settings.wrapper.is_empty());
+    }
+
+    #[test]
+    fn min_memory_above_max() {
+        let mut settings = LaunchSettings::default();
+        settings.set("max-memory", &strings(&["2G"])).unwrap();
+        assert!(settings.set("min-memory", &strings(&["4G"])).is_err());
+        assert_eq!(settings.min_memory, None);
+        settings.set("min-memory", &strings(&["2048"])).unwrap();
+        assert!(settings.set("max-memory", &strings(&["1G"])).is_err());
+        assert_eq!(settings.max_memory, Some(2048));
+        // unsetting one side is always fine
+        settings.set("max-memory", &[]).unwrap();
+        settings.set("min-memory", &strings(&["8G"])).unwrap();
+    }
+
+    #[test]
+    fn single_valued_settings() {
+        let mut settings = LaunchSettings::default();
+        assert!(settings.set("java", &strings(&["a", "b"])).is_err());
+    }
+}
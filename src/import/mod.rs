@@ -0,0 +1,61 @@
+mod prism;
+mod vanilla;
+
+pub use prism::*;
+pub use vanilla::*;
+
+use std::path::Path;
+
+/// Top-level game directory entries left behind when importing.
+const IMPORT_EXCLUDED: [&str; 4] = ["assets", "logs", "crash-reports", "natives"];
+
+/// Brings the contents of another launcher's game directory into `dst`,
+/// either copying them or symlinking each top-level entry.
+pub fn import_game_dir(src: &Path, dst: &Path, link: bool) -> anyhow::Result<()> {
+    if !src.is_dir() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if IMPORT_EXCLUDED.contains(&name.to_string_lossy().as_ref()) {
+            continue;
+        }
+        let target = dst.join(&name);
+        println!("=> {}", name.to_string_lossy());
+        if link {
+            symlink(&entry.path(), &target)?;
+        } else {
+            copy_recursive(&entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_recursive(src: &Path, dst: &Path) -> anyhow::Result<()> {
+    if src.is_dir() {
+        std::fs::create_dir_all(dst)?;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(src, dst)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(src: &Path, dst: &Path) -> anyhow::Result<()> {
+    Ok(std::os::unix::fs::symlink(src, dst)?)
+}
+
+#[cfg(windows)]
+fn symlink(src: &Path, dst: &Path) -> anyhow::Result<()> {
+    if src.is_dir() {
+        Ok(std::os::windows::fs::symlink_dir(src, dst)?)
+    } else {
+        Ok(std::os::windows::fs::symlink_file(src, dst)?)
+    }
+}
@@ -0,0 +1,87 @@
+use crate::loader::{LoaderKind, LoaderSpec};
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Component uids of the loaders we can install.
+const LOADER_COMPONENTS: [(&str, LoaderKind); 4] = [
+    ("net.fabricmc.fabric-loader", LoaderKind::Fabric),
+    ("org.quiltmc.quilt-loader", LoaderKind::Quilt),
+    ("net.minecraftforge", LoaderKind::Forge),
+    ("net.neoforged", LoaderKind::NeoForge),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackComponent {
+    pub uid: String,
+    pub version: Option<String>,
+    #[serde(rename = "cachedVersion")]
+    pub cached_version: Option<String>,
+}
+
+impl PackComponent {
+    fn version(&self) -> Option<&String> {
+        self.version.as_ref().or(self.cached_version.as_ref())
+    }
+}
+
+/// `mmc-pack.json`, the component list of a MultiMC/Prism instance.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MmcPack {
+    pub components: Vec<PackComponent>,
+}
+
+/// A MultiMC or Prism Launcher instance directory.
+#[derive(Debug)]
+pub struct PrismInstance {
+    pub name: String,
+    pub pack: MmcPack,
+    pub minecraft_dir: PathBuf,
+}
+
+impl PrismInstance {
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        let cfg = parse_cfg(&std::fs::read_to_string(dir.join("instance.cfg"))?);
+        let pack: MmcPack = serde_json::from_slice(&std::fs::read(dir.join("mmc-pack.json"))?)?;
+        let name = cfg.get("name").cloned().unwrap_or_else(|| {
+            dir.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        // older MultiMC instances use `minecraft/` rather than `.minecraft/`
+        let minecraft_dir = [".minecraft", "minecraft"]
+            .iter()
+            .map(|d| dir.join(d))
+            .find(|d| d.is_dir())
+            .unwrap_or_else(|| dir.join(".minecraft"));
+        Ok(Self {
+            name,
+            pack,
+            minecraft_dir,
+        })
+    }
+
+    pub fn requirements(&self) -> anyhow::Result<(String, Option<LoaderSpec>)> {
+        let component = |uid: &str| self.pack.components.iter().find(|c| c.uid == uid);
+        let game_version = component("net.minecraft")
+            .and_then(|c| c.version())
+            .ok_or_else(|| anyhow!("instance {} has no minecraft component", self.name))?;
+        let loader = LOADER_COMPONENTS.iter().find_map(|(uid, kind)| {
+            component(uid).map(|c| LoaderSpec {
+                kind: kind.clone(),
+                version: c.version().cloned(),
+            })
+        });
+        Ok((game_version.clone(), loader))
+    }
+}
+
+/// Parses the `key=value` lines of an `instance.cfg`, ignoring sections.
+fn parse_cfg(buf: &str) -> HashMap<String, String> {
+    buf.lines()
+        .filter(|line| !line.starts_with('[') && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
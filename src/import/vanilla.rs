@@ -0,0 +1,237 @@
+use crate::checksum::sha1_hex;
+use crate::db::{Installation, LaunchSettings, Resolution};
+use crate::loader::LoaderKind;
+use crate::minecraft::{
+    AssetIndex, AssetIndexArtifact, RawVersion, Resources, Version, VersionManifest,
+};
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A launch profile from the official launcher's `launcher_profiles.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LauncherProfile {
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type")]
+    pub profile_type: Option<String>,
+    #[serde(rename = "lastVersionId")]
+    pub last_version_id: Option<String>,
+    #[serde(rename = "gameDir")]
+    pub game_dir: Option<PathBuf>,
+    #[serde(rename = "javaArgs")]
+    pub java_args: Option<String>,
+    #[serde(rename = "javaDir")]
+    pub java_dir: Option<PathBuf>,
+    pub resolution: Option<Resolution>,
+}
+
+impl LauncherProfile {
+    /// The version the profile launches; `latest-*` profiles follow the
+    /// manifest.
+    pub fn version_id(&self, manifest: &VersionManifest) -> Option<String> {
+        let id = self
+            .last_version_id
+            .as_deref()
+            .or(self.profile_type.as_deref())?;
+        Some(match id {
+            "latest-release" => manifest.latest.release.clone(),
+            "latest-snapshot" => manifest.latest.snapshot.clone(),
+            id => id.to_string(),
+        })
+    }
+
+    pub fn display_name(&self) -> String {
+        match (self.name.is_empty(), self.profile_type.as_deref()) {
+            (false, _) => self.name.clone(),
+            (true, Some("latest-release")) => "Latest release".to_string(),
+            (true, Some("latest-snapshot")) => "Latest snapshot".to_string(),
+            _ => "Unnamed profile".to_string(),
+        }
+    }
+
+    pub fn settings(&self) -> LaunchSettings {
+        LaunchSettings {
+            java_path: self.java_dir.clone(),
+            jvm_args: self
+                .java_args
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            resolution: self.resolution.clone(),
+            ..LaunchSettings::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LauncherProfiles {
+    profiles: HashMap<String, LauncherProfile>,
+}
+
+/// The `.minecraft` directory of the official launcher.
+#[derive(Debug)]
+pub struct VanillaLauncher {
+    pub root: PathBuf,
+    pub profiles: Vec<LauncherProfile>,
+}
+
+impl VanillaLauncher {
+    /// Where the official launcher keeps its files on this platform.
+    pub fn default_dir() -> PathBuf {
+        if cfg!(target_os = "macos") {
+            dirs::data_dir().unwrap().join("minecraft")
+        } else if cfg!(windows) {
+            dirs::config_dir().unwrap().join(".minecraft")
+        } else {
+            dirs::home_dir().unwrap().join(".minecraft")
+        }
+    }
+
+    pub fn open(root: &Path) -> anyhow::Result<Self> {
+        let buf = std::fs::read(root.join("launcher_profiles.json"))?;
+        let file: LauncherProfiles = serde_json::from_slice(&buf)?;
+        let mut profiles: Vec<LauncherProfile> = file.profiles.into_values().collect();
+        profiles.sort_by_key(|p| p.display_name());
+        Ok(Self {
+            root: root.to_path_buf(),
+            profiles,
+        })
+    }
+
+    /// Loads `id` and its `inheritsFrom` parents, child first, from
+    /// `versions/`. Parents the launcher never downloaded come from the
+    /// manifest.
+    pub async fn version_chain(
+        &self,
+        id: &str,
+        manifest: &VersionManifest,
+    ) -> anyhow::Result<Vec<RawVersion>> {
+        let mut chain: Vec<RawVersion> = Vec::new();
+        let mut next = Some(id.to_string());
+        while let Some(id) = next {
+            if chain.iter().any(|v| v.id == id) {
+                anyhow::bail!("version {} inherits from itself", id);
+            }
+            let local = self
+                .root
+                .join("versions")
+                .join(&id)
+                .join(format!("{}.json", id));
+            let raw: RawVersion = if local.exists() {
+                serde_json::from_slice(&std::fs::read(&local)?)?
+            } else {
+                let vinfo = manifest
+                    .versions
+                    .iter()
+                    .find(|info| info.id == id)
+                    .ok_or_else(|| anyhow!("version {} not found!", id))?;
+                reqwest::get(&vinfo.url)
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?
+            };
+            next = raw.inherits_from.clone();
+            chain.push(raw);
+        }
+        Ok(chain)
+    }
+
+    /// The launcher's copy of an asset index, if it matches `artifact`.
+    pub fn asset_index(&self, artifact: &AssetIndexArtifact) -> Option<AssetIndex> {
+        let path = self
+            .root
+            .join("assets")
+            .join("indexes")
+            .join(format!("{}.json", artifact.id));
+        let buf = std::fs::read(path).ok()?;
+        if sha1_hex(&buf) != artifact.sha1 {
+            return None;
+        }
+        serde_json::from_slice(&buf).ok()
+    }
+
+    /// Links (or copies) the libraries, client jar and assets the launcher
+    /// already has into `inst`, so that installing only downloads what is
+    /// missing. Files are only reused when their hash matches.
+    pub fn seed(
+        &self,
+        inst: &Installation,
+        v: &Version,
+        asset_index: Option<&AssetIndex>,
+    ) -> anyhow::Result<usize> {
+        let game_version = inst.game_version()?;
+        let mut reused = 0;
+        for resource in v.resources() {
+            for artifact in resource.artifacts.iter() {
+                let (src, rel) = match &artifact.path {
+                    Some(path) => (self.root.join("libraries").join(path), path.clone()),
+                    None if resource.name == "client.jar" => (
+                        self.root
+                            .join("versions")
+                            .join(&game_version)
+                            .join(format!("{}.jar", game_version)),
+                        PathBuf::from(&resource.name),
+                    ),
+                    None => continue,
+                };
+                if reuse(&src, &inst.lib_dir.join(rel), artifact.sha1.as_deref())? {
+                    reused += 1;
+                }
+            }
+        }
+        for resource in asset_index.map(|i| i.resources()).unwrap_or_default() {
+            for artifact in resource.artifacts.iter() {
+                let rel = match &artifact.path {
+                    Some(path) => path,
+                    None => continue,
+                };
+                let src = self
+                    .root
+                    .join(rel.strip_prefix(".minecraft").unwrap_or(rel));
+                if reuse(&src, &inst.path.join(rel), artifact.sha1.as_deref())? {
+                    reused += 1;
+                }
+            }
+        }
+        Ok(reused)
+    }
+}
+
+/// Hard links `src` to `dst` when it has the expected hash, falling back to
+/// a copy across filesystems.
+fn reuse(src: &Path, dst: &Path, sha1: Option<&str>) -> anyhow::Result<bool> {
+    if dst.exists() || !src.is_file() {
+        return Ok(false);
+    }
+    if let Some(sha1) = sha1 {
+        if sha1_hex(&std::fs::read(src)?) != sha1 {
+            return Ok(false);
+        }
+    }
+    std::fs::create_dir_all(dst.parent().unwrap())?;
+    if std::fs::hard_link(src, dst).is_err() {
+        std::fs::copy(src, dst)?;
+    }
+    Ok(true)
+}
+
+/// Recognizes the version ids the loader installers give their profiles.
+pub fn detect_loader(id: &str) -> Option<(LoaderKind, String)> {
+    let rsplit_game = |rest: &str| rest.rsplit_once('-').map(|(v, _)| v.to_string());
+    if let Some(rest) = id.strip_prefix("fabric-loader-") {
+        return rsplit_game(rest).map(|v| (LoaderKind::Fabric, v));
+    }
+    if let Some(rest) = id.strip_prefix("quilt-loader-") {
+        return rsplit_game(rest).map(|v| (LoaderKind::Quilt, v));
+    }
+    if let Some(v) = id.strip_prefix("neoforge-") {
+        return Some((LoaderKind::NeoForge, v.to_string()));
+    }
+    id.split_once("-forge-")
+        .map(|(_, v)| (LoaderKind::Forge, v.to_string()))
+}
@@ -0,0 +1,203 @@
+use crate::config::Config;
+use crate::minecraft::JavaVersion;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+
+const JAVA_CACHE_FILE: &str = "java.json";
+
+/// A JVM found on this machine.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JavaInstall {
+    /// The java executable.
+    pub path: PathBuf,
+    pub version: String,
+    pub major_version: u32,
+    pub vendor: String,
+    pub arch: String,
+}
+
+impl JavaInstall {
+    /// Whether this JVM can run a version requiring `java`.
+    pub fn satisfies(&self, java: &JavaVersion) -> bool {
+        self.major_version == java.major_version && self.arch == std::env::consts::ARCH
+    }
+}
+
+/// Probe results keyed by executable, with the executable's modification
+/// time so that upgraded JVMs are probed again.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct JavaCache {
+    installs: HashMap<PathBuf, (u64, JavaInstall)>,
+}
+
+fn java_exe_name() -> &'static str {
+    if cfg!(windows) {
+        "java.exe"
+    } else {
+        "java"
+    }
+}
+
+/// Java homes worth looking at: `JAVA_HOME`, the directories of `PATH`
+/// entries containing java, the usual system locations and SDKMAN.
+fn candidate_homes(config: &Config) -> Vec<PathBuf> {
+    let mut homes = Vec::new();
+    if let Some(home) = std::env::var_os("JAVA_HOME") {
+        homes.push(PathBuf::from(home));
+    }
+    if let Some(path) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&path) {
+            let java = dir.join(java_exe_name());
+            if let Ok(java) = std::fs::canonicalize(java) {
+                // <home>/bin/java
+                if let Some(home) = java.parent().and_then(Path::parent) {
+                    homes.push(home.to_path_buf());
+                }
+            }
+        }
+    }
+    let mut roots = vec![
+        PathBuf::from("/usr/lib/jvm"),
+        PathBuf::from("/Library/Java/JavaVirtualMachines"),
+    ];
+    if let Some(home) = dirs::home_dir() {
+        roots.push(home.join(".sdkman/candidates/java"));
+    }
+    roots.push(config.runtimes_dir());
+    for root in roots.iter() {
+        if let Ok(entries) = std::fs::read_dir(root) {
+            for entry in entries.flatten() {
+                let home = entry.path();
+                let mac_home = home.join("Contents/Home");
+                homes.push(if mac_home.is_dir() { mac_home } else { home });
+            }
+        }
+    }
+    homes
+}
+
+/// Reads `release` in a Java home, falling back to asking the JVM.
+fn probe(java: &Path) -> Option<JavaInstall> {
+    let home = java.parent()?.parent()?;
+    let mut props: HashMap<String, String> = HashMap::new();
+    if let Ok(release) = std::fs::read_to_string(home.join("release")) {
+        for line in release.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                props.insert(
+                    key.trim().to_string(),
+                    value.trim().trim_matches('"').to_string(),
+                );
+            }
+        }
+    }
+    let (version, vendor, arch) = match (
+        props.get("JAVA_VERSION"),
+        props.get("IMPLEMENTOR"),
+        props.get("OS_ARCH"),
+    ) {
+        (Some(version), vendor, Some(arch)) => (
+            version.clone(),
+            vendor.cloned().unwrap_or_default(),
+            arch.clone(),
+        ),
+        _ => {
+            let out = Command::new(java)
+                .args(["-XshowSettings:properties", "-version"])
+                .output()
+                .ok()?;
+            let settings = String::from_utf8_lossy(&out.stderr);
+            let prop = |key: &str| {
+                settings.lines().find_map(|line| {
+                    let (k, v) = line.split_once('=')?;
+                    (k.trim() == key).then(|| v.trim().to_string())
+                })
+            };
+            (
+                prop("java.version")?,
+                prop("java.vendor").unwrap_or_default(),
+                prop("os.arch")?,
+            )
+        }
+    };
+    Some(JavaInstall {
+        path: java.to_path_buf(),
+        major_version: major_version(&version)?,
+        version,
+        vendor,
+        arch: normalize_arch(&arch),
+    })
+}
+
+/// `1.8.0_312` is Java 8, `17.0.3` is Java 17.
+fn major_version(version: &str) -> Option<u32> {
+    let mut parts = version.split(['.', '_', '-', '+']);
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+/// Maps JVM `os.arch` names onto Rust's.
+fn normalize_arch(arch: &str) -> String {
+    match arch {
+        "amd64" | "x86_64" | "x64" => "x86_64",
+        "aarch64" | "arm64" => "aarch64",
+        "x86" | "i386" | "i486" | "i586" | "i686" => "x86",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Finds and probes every JVM on the machine, reusing cached probe results
+/// for executables that haven't changed.
+pub fn detect(config: &Config) -> anyhow::Result<Vec<JavaInstall>> {
+    let cache_file = config.cache_path.join(JAVA_CACHE_FILE);
+    let mut cache: JavaCache = std::fs::read(&cache_file)
+        .ok()
+        .and_then(|buf| serde_json::from_slice(&buf).ok())
+        .unwrap_or_default();
+
+    let mut installs: Vec<JavaInstall> = Vec::new();
+    let mut probed = HashMap::new();
+    for home in candidate_homes(config) {
+        let java = match std::fs::canonicalize(home.join("bin").join(java_exe_name())) {
+            Ok(java) => java,
+            Err(_) => continue,
+        };
+        if probed.contains_key(&java) {
+            continue;
+        }
+        let modified = std::fs::metadata(&java)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)?
+            .as_secs();
+        let install = match cache.installs.get(&java) {
+            Some((mtime, install)) if *mtime == modified => Some(install.clone()),
+            _ => probe(&java),
+        };
+        if let Some(install) = install {
+            probed.insert(java, (modified, install.clone()));
+            installs.push(install);
+        }
+    }
+
+    cache.installs = probed;
+    std::fs::write(&cache_file, serde_json::to_vec(&cache)?)?;
+    installs.sort_by(|a, b| {
+        b.major_version
+            .cmp(&a.major_version)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(installs)
+}
+
+/// A detected JVM matching the major version and architecture `java` asks
+/// for.
+pub fn find_java(config: &Config, java: &JavaVersion) -> anyhow::Result<Option<JavaInstall>> {
+    Ok(detect(config)?
+        .into_iter()
+        .find(|install| install.satisfies(java)))
+}
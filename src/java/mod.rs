@@ -0,0 +1,5 @@
+mod detect;
+mod runtime;
+
+pub use detect::*;
+pub use runtime::*;
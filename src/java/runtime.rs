@@ -0,0 +1,227 @@
+use crate::checksum::sha1_hex;
+use crate::config::Config;
+use crate::minecraft::JavaVersion;
+use crate::modpack::safe_join;
+use anyhow::{anyhow, ensure};
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuntimeDownload {
+    pub sha1: String,
+    pub size: u64,
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuntimeVersionName {
+    pub name: String,
+    pub released: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuntimeEntry {
+    pub manifest: RuntimeDownload,
+    pub version: RuntimeVersionName,
+}
+
+/// `all.json`: the runtime entries of every component, per platform.
+pub type RuntimeIndex = HashMap<String, HashMap<String, Vec<RuntimeEntry>>>;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileDownloads {
+    pub raw: RuntimeDownload,
+    pub lzma: Option<RuntimeDownload>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RuntimeFile {
+    File {
+        #[serde(default)]
+        executable: bool,
+        downloads: FileDownloads,
+    },
+    Directory,
+    Link {
+        target: String,
+    },
+}
+
+/// The file listing of one runtime.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuntimeManifest {
+    pub files: HashMap<String, RuntimeFile>,
+}
+
+/// The key Mojang files this platform's runtimes under, if it has any.
+pub fn platform() -> Option<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Some("linux"),
+        ("linux", "x86") => Some("linux-i386"),
+        ("macos", "x86_64") => Some("mac-os"),
+        ("macos", "aarch64") => Some("mac-os-arm64"),
+        ("windows", "x86_64") => Some("windows-x64"),
+        ("windows", "x86") => Some("windows-x86"),
+        ("windows", "aarch64") => Some("windows-arm64"),
+        _ => None,
+    }
+}
+
+/// The java executable inside a runtime installed at `dir`.
+pub fn java_executable(dir: &Path) -> PathBuf {
+    if cfg!(target_os = "macos") {
+        dir.join("jre.bundle/Contents/Home/bin/java")
+    } else if cfg!(windows) {
+        dir.join("bin").join("javaw.exe")
+    } else {
+        dir.join("bin").join("java")
+    }
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(url: &str) -> anyhow::Result<T> {
+    Ok(reqwest::get(url).await?.error_for_status()?.json().await?)
+}
+
+/// Installs the Mojang runtime for `java` into the shared runtimes directory,
+/// unless the current build of it is already there, and returns its java
+/// executable.
+pub async fn ensure_runtime(config: &Config, java: &JavaVersion) -> anyhow::Result<PathBuf> {
+    let platform = platform().ok_or_else(|| {
+        anyhow!(
+            "Mojang has no Java runtimes for {}-{}",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )
+    })?;
+    let index: RuntimeIndex = fetch_json(&config.java_runtime_manifest_url).await?;
+    let entry = index
+        .get(platform)
+        .and_then(|components| components.get(&java.component))
+        .and_then(|entries| entries.first())
+        .ok_or_else(|| anyhow!("no {} runtime for {}", java.component, platform))?;
+
+    let dir = config.runtimes_dir().join(&java.component);
+    let marker = config
+        .runtimes_dir()
+        .join(format!("{}.sha1", java.component));
+    if std::fs::read_to_string(&marker).ok().as_deref() == Some(entry.manifest.sha1.as_str()) {
+        return Ok(java_executable(&dir));
+    }
+
+    println!(
+        "Installing Java runtime {} ({})",
+        &java.component, &entry.version.name
+    );
+    let buf = reqwest::get(&entry.manifest.url)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    ensure!(
+        sha1_hex(&buf) == entry.manifest.sha1,
+        "checksum verification failed for the {} manifest",
+        java.component
+    );
+    let manifest: RuntimeManifest = serde_json::from_slice(&buf)?;
+    install(&manifest, &dir).await?;
+    std::fs::write(&marker, &entry.manifest.sha1)?;
+    Ok(java_executable(&dir))
+}
+
+/// Lays out a runtime's files in `dir`, only downloading files that are
+/// missing or don't match their hash.
+pub async fn install(manifest: &RuntimeManifest, dir: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut paths: Vec<&String> = manifest.files.keys().collect();
+    // parents sort before their children
+    paths.sort();
+
+    let mut downloads = Vec::new();
+    for path in paths.iter() {
+        let dst = safe_join(dir, path)?;
+        match &manifest.files[*path] {
+            RuntimeFile::Directory => std::fs::create_dir_all(&dst)?,
+            RuntimeFile::File {
+                executable,
+                downloads: files,
+            } => downloads.push(install_file(files, dst, *executable)),
+            RuntimeFile::Link { target } => link(target, &dst)?,
+        }
+    }
+    try_join_all(downloads).await?;
+    Ok(())
+}
+
+async fn install_file(files: &FileDownloads, dst: PathBuf, executable: bool) -> anyhow::Result<()> {
+    if let Ok(existing) = std::fs::read(&dst) {
+        if sha1_hex(&existing) == files.raw.sha1 {
+            return Ok(());
+        }
+    }
+    let buf = match &files.lzma {
+        Some(lzma) => {
+            let packed = reqwest::get(&lzma.url)
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            let mut data = Vec::new();
+            lzma_rs::lzma_decompress(&mut Cursor::new(packed), &mut data)
+                .map_err(|err| anyhow!("invalid lzma download {} ({:?})", &lzma.url, err))?;
+            data
+        }
+        None => reqwest::get(&files.raw.url)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec(),
+    };
+    ensure!(
+        sha1_hex(&buf) == files.raw.sha1,
+        "checksum verification failed for {:?}",
+        &dst
+    );
+    if let Some(parent) = dst.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&dst, &buf)?;
+    if executable {
+        set_executable(&dst)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn link(target: &str, dst: &Path) -> anyhow::Result<()> {
+    if std::fs::symlink_metadata(dst).is_ok() {
+        std::fs::remove_file(dst)?;
+    }
+    if let Some(parent) = dst.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::os::unix::fs::symlink(target, dst)?;
+    Ok(())
+}
+
+/// Runtime links only exist in the macOS and Linux runtimes.
+#[cfg(not(unix))]
+fn link(_target: &str, _dst: &Path) -> anyhow::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,436 @@
+use crate::db::{Installation, LaunchSettings};
+use crate::minecraft::Version;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const CRASH_REPORTS_DIR: &str = "crash-reports";
+const HS_ERR_PREFIX: &str = "hs_err_pid";
+/// Lines of evidence quoted per finding.
+const MAX_EVIDENCE: usize = 5;
+
+/// What a crash was most likely caused by, and what to do about it.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub cause: String,
+    pub advice: String,
+    pub evidence: Vec<String>,
+}
+
+/// What the rules know about the launch besides the crash output.
+pub struct CrashContext<'a> {
+    pub name: &'a str,
+    pub version: &'a Version,
+    pub settings: &'a LaunchSettings,
+}
+
+type Rule = fn(&str, &CrashContext) -> Option<Finding>;
+
+const RULES: [Rule; 5] = [
+    wrong_java,
+    missing_dependency,
+    out_of_memory,
+    graphics_driver,
+    mixin_failure,
+];
+
+/// The newest crash report or JVM error log written since `since`.
+pub fn find_report(game_dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    let crash_reports = std::fs::read_dir(game_dir.join(CRASH_REPORTS_DIR))
+        .into_iter()
+        .flatten();
+    let hs_errs = std::fs::read_dir(game_dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| {
+            entry.as_ref().is_ok_and(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(HS_ERR_PREFIX)
+            })
+        });
+    crash_reports
+        .chain(hs_errs)
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            (entry.file_type().ok()?.is_file() && modified >= since)
+                .then(|| (modified, entry.path()))
+        })
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// Runs every rule over the crash output.
+pub fn analyze(text: &str, ctx: &CrashContext) -> Vec<Finding> {
+    RULES.iter().filter_map(|rule| rule(text, ctx)).collect()
+}
+
+/// Explains why the game of `inst` exited with an error, from its crash
+/// report or JVM error log and the launch log.
+pub fn explain_crash(
+    inst: &Installation,
+    v: &Version,
+    settings: &LaunchSettings,
+    log: &Path,
+    since: SystemTime,
+) -> anyhow::Result<()> {
+    let report = find_report(&inst.game_dir(), since);
+    let mut text = std::fs::read_to_string(log).unwrap_or_default();
+    if let Some(report) = &report {
+        println!("The game crashed, see {}", report.display());
+        text.push('\n');
+        text.push_str(&String::from_utf8_lossy(&std::fs::read(report)?));
+    }
+    let ctx = CrashContext {
+        name: &inst.name,
+        version: v,
+        settings,
+    };
+    let findings = analyze(&text, &ctx);
+    if findings.is_empty() {
+        println!("No known cause found, the log is in {}", log.display());
+    }
+    for finding in findings {
+        println!("Likely cause: {}", finding.cause);
+        for line in finding.evidence.iter() {
+            println!("    {}", line);
+        }
+        println!("  {}", finding.advice);
+    }
+    Ok(())
+}
+
+/// Trimmed lines containing any of `needles`.
+fn lines_with(text: &str, needles: &[&str]) -> Vec<String> {
+    let mut lines: Vec<String> = text
+        .lines()
+        .filter(|line| needles.iter().any(|needle| line.contains(needle)))
+        .map(|line| line.trim().to_string())
+        .collect();
+    lines.dedup();
+    lines.truncate(MAX_EVIDENCE);
+    lines
+}
+
+/// The number following `prefix` in `text`, e.g. `61` in `class file
+/// version 61.0`.
+fn number_after(text: &str, prefix: &str) -> Option<u32> {
+    let start = text.find(prefix)? + prefix.len();
+    let digits: String = text[start..]
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn java_advice(ctx: &CrashContext, needed: u32) -> String {
+    match &ctx.settings.java_path {
+        Some(java) => format!(
+            "{} is set to run on {}, point it at Java {} with `mcl instance set {} java <path>` or unset it with `mcl instance set {} java`",
+            ctx.name,
+            java.display(),
+            needed,
+            ctx.name,
+            ctx.name
+        ),
+        // `java install` fetches the Java of the game version, not the
+        // newer one a mod may need
+        None if needed > ctx.version.java_version.major_version => format!(
+            "install Java {} and point {} at it with `mcl instance set {} java <path>`",
+            needed, ctx.name, ctx.name
+        ),
+        None => format!(
+            "install Java {} (`mcl java install {}` fetches the one the game wants)",
+            needed, ctx.name
+        ),
+    }
+}
+
+fn wrong_java(text: &str, ctx: &CrashContext) -> Option<Finding> {
+    let required = ctx.version.java_version.major_version;
+    if text.contains("UnsupportedClassVersionError") {
+        // class file versions are the Java version plus 44
+        let needed = number_after(text, "class file version")?.checked_sub(44)?;
+        return Some(Finding {
+            cause: format!(
+                "some code needs Java {}, newer than the Java the game ran on",
+                needed
+            ),
+            advice: java_advice(ctx, needed.max(required)),
+            evidence: lines_with(text, &["UnsupportedClassVersionError"]),
+        });
+    }
+    // crash reports list the JVM under "System Details"
+    let line = text
+        .lines()
+        .find(|line| line.trim_start().starts_with("Java Version:"))?;
+    let version = line.split(':').nth(1)?.trim();
+    let mut parts = version.split(['.', '_', ',', ' ']);
+    let major: u32 = match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok()?,
+        major => major,
+    };
+    (major != required).then(|| Finding {
+        cause: format!(
+            "the game ran on Java {}, but {} wants Java {}",
+            major, &ctx.version.id, required
+        ),
+        advice: java_advice(ctx, required),
+        evidence: vec![line.trim().to_string()],
+    })
+}
+
+fn missing_dependency(text: &str, ctx: &CrashContext) -> Option<Finding> {
+    let evidence = lines_with(
+        text,
+        &[
+            // Fabric and Quilt
+            "which is missing",
+            "requires any version of",
+            "requires version",
+            // Forge and NeoForge
+            "Actual version: '[MISSING]'",
+            "Missing or unsupported mandatory dependencies",
+        ],
+    );
+    (!evidence.is_empty()).then(|| Finding {
+        cause: "a mod is missing a dependency".to_string(),
+        advice: format!(
+            "add the missing mods with `mcl mod add {} <project>`, `mcl mod check {}` lists what else is missing",
+            ctx.name, ctx.name
+        ),
+        evidence,
+    })
+}
+
+fn out_of_memory(text: &str, ctx: &CrashContext) -> Option<Finding> {
+    if text.contains("There is insufficient memory for the Java Runtime Environment")
+        || text.contains("Native memory allocation (mmap) failed")
+    {
+        return Some(Finding {
+            cause: "the machine ran out of memory".to_string(),
+            advice: format!(
+                "close other programs or lower the heap with `mcl instance set {} max-memory <size>`",
+                ctx.name
+            ),
+            evidence: lines_with(text, &["insufficient memory", "memory allocation"]),
+        });
+    }
+    if !text.contains("java.lang.OutOfMemoryError") {
+        return None;
+    }
+    let current = match ctx.settings.max_memory {
+        Some(max) => format!(" (currently {}M)", max),
+        None => String::new(),
+    };
+    Some(Finding {
+        cause: "the game ran out of heap memory".to_string(),
+        advice: format!(
+            "raise the heap{}, e.g. `mcl instance set {} max-memory 4G`",
+            current, ctx.name
+        ),
+        evidence: lines_with(text, &["java.lang.OutOfMemoryError"]),
+    })
+}
+
+fn graphics_driver(text: &str, ctx: &CrashContext) -> Option<Finding> {
+    let evidence = lines_with(
+        text,
+        &[
+            "Pixel format not accelerated",
+            "GLFW error 65542",
+            "GLFW error 65543",
+            "The driver does not appear to support OpenGL",
+            "Couldn't set pixel format",
+            "OpenGL 3.2",
+            // native frames of the AMD, Intel and NVIDIA OpenGL drivers
+            "atio6axx.dll",
+            "ig9icd64.dll",
+            "ig75icd64.dll",
+            "nvoglv64.dll",
+            "libnvidia-glcore.so",
+        ],
+    );
+    (!evidence.is_empty()).then(|| Finding {
+        cause: "the graphics driver failed or doesn't support the OpenGL version the game needs"
+            .to_string(),
+        advice: format!(
+            "update the graphics driver; with two GPUs, run the game on the dedicated one (e.g. `mcl instance set {} wrapper prime-run`)",
+            ctx.name
+        ),
+        evidence,
+    })
+}
+
+fn mixin_failure(text: &str, ctx: &CrashContext) -> Option<Finding> {
+    let evidence = lines_with(
+        text,
+        &[
+            "MixinApplyError",
+            "MixinTransformerError",
+            "InvalidMixinException",
+            "Mixin apply failed",
+            "Mixin prepare failed",
+            "Critical injection failure",
+        ],
+    );
+    if evidence.is_empty() {
+        return None;
+    }
+    // "Mixin [sodium.mixins.json:...] from mod sodium failed"
+    let culprit = text.split("from mod ").nth(1).and_then(|rest| {
+        let id: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+            .collect();
+        (!id.is_empty()).then_some(id)
+    });
+    let project = culprit.as_deref().unwrap_or("<project>");
+    Some(Finding {
+        cause: match &culprit {
+            Some(id) => format!("the mod {} failed to patch the game (mixin)", id),
+            None => "a mod failed to patch the game (mixin)".to_string(),
+        },
+        advice: format!(
+            "mixin failures usually mean a mod doesn't match this game or loader version, update or remove it with `mcl mod update {} {}` or `mcl mod remove {} {}`",
+            ctx.name,
+            project,
+            ctx.name,
+            project
+        ),
+        evidence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION: &str = r#"{
+        "id": "1.18.2",
+        "downloads": {},
+        "javaVersion": {"component": "java-runtime-gamma", "majorVersion": 17},
+        "libraries": [],
+        "mainClass": "net.minecraft.client.main.Main",
+        "releaseTime": "2022-02-28T10:42:45+00:00",
+        "time": "2022-02-28T10:42:45+00:00",
+        "type": "release"
+    }"#;
+
+    fn analyze_with(text: &str, settings: &LaunchSettings) -> Vec<Finding> {
+        let version: Version = serde_json::from_str(VERSION).unwrap();
+        let ctx = CrashContext {
+            name: "pack",
+            version: &version,
+            settings,
+        };
+        analyze(text, &ctx)
+    }
+
+    fn causes(text: &str) -> Vec<String> {
+        analyze_with(text, &LaunchSettings::default())
+            .into_iter()
+            .map(|finding| finding.cause)
+            .collect()
+    }
+
+    #[test]
+    fn number_after_prefix() {
+        assert_eq!(
+            number_after(
+                "compiled by a more recent version (class file version 61.0)",
+                "class file version"
+            ),
+            Some(61)
+        );
+        assert_eq!(
+            number_after("class file version", "class file version"),
+            None
+        );
+        assert_eq!(number_after("no version here", "class file version"), None);
+    }
+
+    #[test]
+    fn nothing_known() {
+        assert!(causes("[main/INFO]: Stopping!").is_empty());
+    }
+
+    #[test]
+    fn mod_needing_newer_java() {
+        let findings = analyze_with(
+            "java.lang.UnsupportedClassVersionError: a/b/C has been compiled by a more recent version of the Java Runtime (class file version 65.0)",
+            &LaunchSettings::default(),
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].cause,
+            "some code needs Java 21, newer than the Java the game ran on"
+        );
+        // `java install` would fetch Java 17, the one of the version
+        assert!(findings[0]
+            .advice
+            .starts_with("install Java 21 and point pack at it"));
+    }
+
+    #[test]
+    fn game_on_older_java() {
+        let findings = analyze_with(
+            "java.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main (class file version 61.0)",
+            &LaunchSettings::default(),
+        );
+        assert!(findings[0].advice.contains("`mcl java install pack`"));
+    }
+
+    #[test]
+    fn wrong_java_from_crash_report() {
+        assert_eq!(
+            causes("-- System Details --\n\tJava Version: 1.8.0_312, Oracle Corporation"),
+            ["the game ran on Java 8, but 1.18.2 wants Java 17"]
+        );
+        assert!(causes("\tJava Version: 17.0.3, Eclipse Adoptium").is_empty());
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let findings = analyze_with(
+            "Mod 'Sodium Extra' (sodium-extra) 0.4.10 requires any version of sodium, which is missing!",
+            &LaunchSettings::default(),
+        );
+        assert_eq!(findings[0].cause, "a mod is missing a dependency");
+        assert_eq!(findings[0].evidence.len(), 1);
+    }
+
+    #[test]
+    fn out_of_heap_memory() {
+        let settings = LaunchSettings {
+            max_memory: Some(2048),
+            ..LaunchSettings::default()
+        };
+        let findings = analyze_with("java.lang.OutOfMemoryError: Java heap space", &settings);
+        assert_eq!(findings[0].cause, "the game ran out of heap memory");
+        assert!(findings[0].advice.contains("(currently 2048M)"));
+    }
+
+    #[test]
+    fn graphics_driver() {
+        assert_eq!(
+            causes("GLFW error 65542: WGL: The driver does not appear to support OpenGL"),
+            ["the graphics driver failed or doesn't support the OpenGL version the game needs"]
+        );
+    }
+
+    #[test]
+    fn mixin_failure_names_the_mod() {
+        let findings = analyze_with(
+            "org.spongepowered.asm.mixin.injection.throwables.InjectionError: Critical injection failure: Mixin [sodium.mixins.json:Foo] from mod sodium failed",
+            &LaunchSettings::default(),
+        );
+        assert_eq!(
+            findings[0].cause,
+            "the mod sodium failed to patch the game (mixin)"
+        );
+        assert!(findings[0].advice.contains("`mcl mod update pack sodium`"));
+    }
+}
@@ -0,0 +1,258 @@
+use chrono::{Local, TimeZone};
+use std::fmt;
+use std::str::FromStr;
+
+const EVENT_START: &str = "<log4j:Event";
+const EVENT_END: &str = "</log4j:Event>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl FromStr for Level {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TRACE" => Ok(Level::Trace),
+            "DEBUG" => Ok(Level::Debug),
+            "INFO" => Ok(Level::Info),
+            "WARN" => Ok(Level::Warn),
+            "ERROR" => Ok(Level::Error),
+            "FATAL" => Ok(Level::Fatal),
+            _ => anyhow::bail!("unknown log level {}", s),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+            Level::Fatal => "FATAL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One log4j event as printed by the XML layout of the game's logging
+/// config.
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    /// Milliseconds since the epoch.
+    pub timestamp: Option<i64>,
+    pub level: Level,
+    pub thread: String,
+    pub logger: String,
+    pub message: String,
+    pub throwable: Option<String>,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(time) = self
+            .timestamp
+            .and_then(|ms| Local.timestamp_millis_opt(ms).single())
+        {
+            write!(f, "[{}] ", time.format("%H:%M:%S"))?;
+        }
+        // loggers are class names, the simple name is enough to read along
+        let logger = self.logger.rsplit('.').next().unwrap_or_default();
+        write!(
+            f,
+            "[{}/{}] [{}]: {}",
+            &self.thread, self.level, logger, &self.message
+        )?;
+        if let Some(throwable) = &self.throwable {
+            write!(f, "\n{}", throwable.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// A line of game output: a parsed event, or anything printed around the
+/// logger.
+#[derive(Debug, Clone)]
+pub enum LogLine {
+    Record(LogRecord),
+    Raw(String),
+}
+
+/// Reassembles log4j XML events from the lines of the game's stdout.
+#[derive(Debug, Default)]
+pub struct Log4jParser {
+    event: Option<String>,
+}
+
+impl Log4jParser {
+    /// Takes the next line, returning what it completed, if anything.
+    pub fn feed(&mut self, line: &str) -> Option<LogLine> {
+        match self.event.as_mut() {
+            Some(event) => {
+                event.push('\n');
+                event.push_str(line);
+            }
+            None if line.trim_start().starts_with(EVENT_START) => {
+                self.event = Some(line.to_string());
+            }
+            None => return Some(LogLine::Raw(line.to_string())),
+        }
+        if !line.contains(EVENT_END) {
+            return None;
+        }
+        let event = self.event.take()?;
+        Some(match parse_event(&event) {
+            Some(record) => LogLine::Record(record),
+            None => LogLine::Raw(event),
+        })
+    }
+
+    /// Whatever is left of an event cut off by the game exiting.
+    pub fn finish(&mut self) -> Option<LogLine> {
+        self.event.take().map(LogLine::Raw)
+    }
+}
+
+fn parse_event(event: &str) -> Option<LogRecord> {
+    let start = event.find(EVENT_START)? + EVENT_START.len();
+    let tag = &event[start..start + event[start..].find('>')?];
+    Some(LogRecord {
+        timestamp: attribute(tag, "timestamp").and_then(|t| t.parse().ok()),
+        level: attribute(tag, "level")?.parse().ok()?,
+        thread: attribute(tag, "thread").unwrap_or_default(),
+        logger: attribute(tag, "logger").unwrap_or_default(),
+        message: element(event, "log4j:Message").unwrap_or_default(),
+        throwable: element(event, "log4j:Throwable"),
+    })
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let key = format!(" {}=\"", name);
+    let start = tag.find(&key)? + key.len();
+    let end = start + tag[start..].find('"')?;
+    Some(unescape(&tag[start..end]))
+}
+
+/// The text of an element, joining its CDATA sections.
+fn element(event: &str, name: &str) -> Option<String> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let start = event.find(&open)? + open.len();
+    let end = start + event[start..].find(&close)?;
+    let mut content = &event[start..end];
+    if !content.contains("<![CDATA[") {
+        return Some(unescape(content));
+    }
+    let mut text = String::new();
+    while let Some(cdata) = content.find("<![CDATA[") {
+        let rest = &content[cdata + 9..];
+        let len = rest.find("]]>").unwrap_or(rest.len());
+        text.push_str(&rest[..len]);
+        content = rest.get(len + 3..).unwrap_or_default();
+    }
+    Some(text)
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(lines: &str) -> Vec<LogLine> {
+        let mut parser = Log4jParser::default();
+        let mut out: Vec<LogLine> = lines.lines().filter_map(|l| parser.feed(l)).collect();
+        out.extend(parser.finish());
+        out
+    }
+
+    fn record(line: &LogLine) -> &LogRecord {
+        match line {
+            LogLine::Record(record) => record,
+            LogLine::Raw(text) => panic!("not parsed: {}", text),
+        }
+    }
+
+    #[test]
+    fn multi_line_event() {
+        let out = feed_all(
+            r#"plain line
+<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1666176000000" level="INFO" thread="Render thread">
+  <log4j:Message><![CDATA[Setting user: Notch]]></log4j:Message>
+</log4j:Event>
+after"#,
+        );
+        assert_eq!(out.len(), 3);
+        assert!(matches!(&out[0], LogLine::Raw(text) if text == "plain line"));
+        let record = record(&out[1]);
+        assert_eq!(record.level, Level::Info);
+        assert_eq!(record.thread, "Render thread");
+        assert_eq!(record.logger, "net.minecraft.client.Minecraft");
+        assert_eq!(record.timestamp, Some(1666176000000));
+        assert_eq!(record.message, "Setting user: Notch");
+        assert!(record.throwable.is_none());
+        assert!(matches!(&out[2], LogLine::Raw(text) if text == "after"));
+    }
+
+    #[test]
+    fn cdata_and_escaped_text() {
+        let out = feed_all(
+            r#"<log4j:Event logger="a.B" timestamp="0" level="WARN" thread="Sound &amp; engine"><log4j:Message><![CDATA[<friends> & ]]><![CDATA[more]]></log4j:Message></log4j:Event>
+<log4j:Event logger="a.B" timestamp="0" level="WARN" thread="main"><log4j:Message>a &lt;b&gt;</log4j:Message></log4j:Event>"#,
+        );
+        assert_eq!(record(&out[0]).thread, "Sound & engine");
+        assert_eq!(record(&out[0]).message, "<friends> & more");
+        assert_eq!(record(&out[1]).message, "a <b>");
+    }
+
+    #[test]
+    fn throwable() {
+        let out = feed_all(
+            r#"<log4j:Event logger="net.minecraft.server.Main" timestamp="0" level="ERROR" thread="main">
+  <log4j:Message><![CDATA[Crash!]]></log4j:Message>
+  <log4j:Throwable><![CDATA[java.lang.RuntimeException: boom
+	at a.b.C.d(C.java:1)
+]]></log4j:Throwable>
+</log4j:Event>"#,
+        );
+        let record = record(&out[0]);
+        assert_eq!(record.level, Level::Error);
+        assert_eq!(
+            record.throwable.as_deref(),
+            Some("java.lang.RuntimeException: boom\n\tat a.b.C.d(C.java:1)\n")
+        );
+        assert!(record.to_string().ends_with(
+            "[main/ERROR] [Main]: Crash!\njava.lang.RuntimeException: boom\n\tat a.b.C.d(C.java:1)"
+        ));
+    }
+
+    #[test]
+    fn unknown_level_stays_raw() {
+        let event = r#"<log4j:Event logger="a.B" timestamp="0" level="NOTICE" thread="main"><log4j:Message>hi</log4j:Message></log4j:Event>"#;
+        let out = feed_all(event);
+        assert!(matches!(&out[..], [LogLine::Raw(text)] if text == event));
+    }
+
+    #[test]
+    fn cut_off_event() {
+        let out =
+            feed_all(r#"<log4j:Event logger="a.B" timestamp="0" level="INFO" thread="main">"#);
+        assert!(matches!(&out[..], [LogLine::Raw(_)]));
+    }
+}
@@ -0,0 +1,454 @@
+mod crash;
+mod log4j;
+mod supervise;
+
+pub use crash::*;
+pub use supervise::*;
+
+use crate::auth::{Account, AccountKind};
+use crate::checksum::sha1_hex;
+use crate::config::Config;
+use crate::db::{Installation, LaunchSettings};
+use crate::java;
+use crate::minecraft::{rules_allow, Argument, ArgumentValue, AssetIndex, Library, Version};
+use crate::modpack::safe_join;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+const LAUNCHER_NAME: &str = "mcl";
+const NATIVES_DIR: &str = "natives";
+const ASSETS_DIR: &str = ".minecraft/assets";
+const LOGGING_DIR: &str = "logging";
+/// Where mcl keeps the output of the game, next to the game directory.
+pub const LOGS_DIR: &str = "logs";
+
+/// The player the game is started as.
+#[derive(Debug, Clone)]
+pub struct LaunchAuth {
+    pub player_name: String,
+    pub uuid: String,
+    pub access_token: String,
+    pub user_type: String,
+    /// JVM arguments the account needs, like authlib-injector's.
+    pub jvm_args: Vec<String>,
+}
+
+impl From<&Account> for LaunchAuth {
+    fn from(account: &Account) -> Self {
+        Self {
+            player_name: account.name.clone(),
+            uuid: account.uuid.clone(),
+            access_token: account.tokens.access_token.clone(),
+            user_type: match account.kind {
+                AccountKind::Microsoft => "msa".to_string(),
+                AccountKind::Yggdrasil => "mojang".to_string(),
+                AccountKind::Offline => "legacy".to_string(),
+            },
+            jvm_args: Vec::new(),
+        }
+    }
+}
+
+/// A fully resolved game command line.
+#[derive(Debug, Clone)]
+pub struct LaunchCommand {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub dir: PathBuf,
+}
+
+impl LaunchCommand {
+    pub fn command(&self) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(&self.env)
+            .current_dir(&self.dir);
+        command
+    }
+}
+
+impl fmt::Display for LaunchCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in self.env.iter() {
+            write!(f, "{}={} ", name, quote(value))?;
+        }
+        write!(f, "{}", quote(&self.program.to_string_lossy()))?;
+        for arg in self.args.iter() {
+            write!(f, " {}", quote(arg))?;
+        }
+        Ok(())
+    }
+}
+
+fn quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%${}".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// The Java an installation runs on: the configured executable, a matching
+/// JVM on this machine, or else the Mojang runtime, installed if needed.
+pub async fn java_for(
+    config: &Config,
+    settings: &LaunchSettings,
+    v: &Version,
+) -> anyhow::Result<PathBuf> {
+    if let Some(java) = &settings.java_path {
+        return Ok(java.clone());
+    }
+    match java::find_java(config, &v.java_version)? {
+        Some(install) => Ok(install.path),
+        None => java::ensure_runtime(config, &v.java_version).await,
+    }
+}
+
+fn classpath_separator() -> &'static str {
+    if cfg!(windows) {
+        ";"
+    } else {
+        ":"
+    }
+}
+
+/// Where a library's jar lives in the library directory, mirroring the
+/// layout `Resource::install_to` writes.
+fn library_path(lib: &Library) -> Option<PathBuf> {
+    match &lib.downloads {
+        Some(downloads) => downloads.artifact.as_ref().map(|artifact| {
+            artifact
+                .path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(&lib.name))
+        }),
+        None if lib.natives.is_some() => None,
+        None => lib.coordinate().ok().map(|c| c.path()),
+    }
+}
+
+fn native_path(lib: &Library) -> Option<PathBuf> {
+    let classifier = lib.native_classifier()?;
+    let downloads = match &lib.downloads {
+        Some(downloads) => downloads,
+        None => return lib.native_coordinate().map(|c| c.path()),
+    };
+    let artifact = downloads.classifiers.as_ref()?.get(&classifier)?;
+    Some(
+        artifact
+            .path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&lib.name)),
+    )
+}
+
+/// The client jar as the game is started with it, named after the launched
+/// version like the official launcher does: modern Forge and NeoForge find
+/// it by that name (`-DignoreList=...,${version_name}.jar`).
+fn client_jar(inst: &Installation) -> PathBuf {
+    inst.lib_dir.join(format!("{}.jar", &inst.version))
+}
+
+fn logging_config_path(inst: &Installation, v: &Version) -> Option<PathBuf> {
+    let config = v.logging.as_ref()?.client.as_ref()?;
+    Some(inst.path.join(LOGGING_DIR).join(&config.file.id))
+}
+
+/// Puts the files the game expects next to the installed ones: the client
+/// jar under the version's name, extracted natives, the logging config, the
+/// asset index under its id and, for old versions, the assets under their
+/// names.
+pub async fn prepare(inst: &Installation, v: &Version) -> anyhow::Result<()> {
+    let installed = inst.lib_dir.join("client.jar");
+    let client = client_jar(inst);
+    let size = |path: &Path| std::fs::metadata(path).map(|meta| meta.len()).ok();
+    if size(&client) != size(&installed) {
+        std::fs::remove_file(&client).ok();
+        if std::fs::hard_link(&installed, &client).is_err() {
+            std::fs::copy(&installed, &client)?;
+        }
+    }
+
+    if let (Some(path), Some(config)) = (
+        logging_config_path(inst, v),
+        v.logging.as_ref().and_then(|l| l.client.as_ref()),
+    ) {
+        let current = std::fs::read(&path)
+            .map(|buf| sha1_hex(&buf) == config.file.sha1)
+            .unwrap_or(false);
+        if !current {
+            let buf = reqwest::get(&config.file.url)
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            anyhow::ensure!(
+                sha1_hex(&buf) == config.file.sha1,
+                "checksum verification failed for {}",
+                &config.file.id
+            );
+            std::fs::create_dir_all(inst.path.join(LOGGING_DIR))?;
+            std::fs::write(&path, &buf)?;
+        }
+    }
+
+    let natives_dir = inst.path.join(NATIVES_DIR);
+    std::fs::create_dir_all(&natives_dir)?;
+    for lib in v.libraries.iter().filter(|lib| lib.is_allowed()) {
+        if let Some(path) = native_path(lib) {
+            let exclude = lib
+                .extract
+                .as_ref()
+                .map(|e| e.exclude.clone())
+                .unwrap_or_default();
+            extract_natives(&inst.lib_dir.join(path), &natives_dir, &exclude)?;
+        }
+    }
+
+    // versions without an `assetIndex` come with no assets to set up
+    if v.asset_index.is_none() {
+        return Ok(());
+    }
+    let assets_dir = inst.path.join(ASSETS_DIR);
+    let indexes_dir = assets_dir.join("indexes");
+    std::fs::create_dir_all(&indexes_dir)?;
+    let index_path = indexes_dir.join(format!("{}.json", &v.assets));
+    let buf = std::fs::read(inst.path.join("asset_index.json"))?;
+    if !index_path.exists() {
+        std::fs::write(&index_path, &buf)?;
+    }
+    if is_virtual(&v.assets) {
+        let index: AssetIndex = serde_json::from_slice(&buf)?;
+        let virtual_dir = assets_dir.join("virtual").join(&v.assets);
+        for (name, asset) in index.objects.iter() {
+            let dst = safe_join(&virtual_dir, name)?;
+            if dst.exists() {
+                continue;
+            }
+            if let Some(parent) = dst.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let src = assets_dir
+                .join("objects")
+                .join(&asset.hash[..2])
+                .join(&asset.hash);
+            std::fs::copy(src, dst)?;
+        }
+    }
+    Ok(())
+}
+
+/// Asset indexes from before 1.7.10 are read by file name, not hash.
+fn is_virtual(assets: &str) -> bool {
+    assets == "legacy" || assets == "pre-1.6"
+}
+
+fn extract_natives(jar: &Path, dir: &Path, exclude: &[String]) -> anyhow::Result<()> {
+    let mut archive = ZipArchive::new(File::open(jar)?)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || exclude.iter().any(|prefix| name.starts_with(prefix)) {
+            continue;
+        }
+        let dst = safe_join(dir, &name)?;
+        if let Some(parent) = dst.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::io::copy(&mut entry, &mut File::create(dst)?)?;
+    }
+    Ok(())
+}
+
+/// Replaces the `${name}` placeholders of an argument, leaving unknown ones
+/// as they are.
+fn substitute(arg: &str, vars: &HashMap<&str, String>) -> String {
+    let mut out = String::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        out.push_str(&rest[..start]);
+        match vars.get(&rest[start + 2..end]) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn expand(args: &[Argument], features: &HashMap<String, bool>) -> Vec<String> {
+    let mut out = Vec::new();
+    for arg in args.iter() {
+        match arg {
+            Argument::Plain(value) => out.push(value.clone()),
+            Argument::Conditional { rules, value } if rules_allow(rules, features) => match value {
+                ArgumentValue::Single(value) => out.push(value.clone()),
+                ArgumentValue::Many(values) => out.extend(values.iter().cloned()),
+            },
+            Argument::Conditional { .. } => {}
+        }
+    }
+    out
+}
+
+/// The JVM arguments versions without `arguments` (before 1.13) leave to
+/// the launcher.
+fn legacy_jvm_args() -> Vec<String> {
+    let mut args = Vec::new();
+    if cfg!(target_os = "macos") {
+        args.push("-XstartOnFirstThread".to_string());
+    }
+    args.extend(
+        [
+            "-Djava.library.path=${natives_directory}",
+            "-cp",
+            "${classpath}",
+        ]
+        .iter()
+        .map(|s| s.to_string()),
+    );
+    args
+}
+
+/// Builds the command starting an installation. The command is
+/// `[wrapper...] java [heap] [version JVM args] [logging config] [account JVM
+/// args] [extra JVM args] main-class [game args]`.
+pub fn build(
+    inst: &Installation,
+    v: &Version,
+    settings: &LaunchSettings,
+    java: &Path,
+    auth: &LaunchAuth,
+) -> anyhow::Result<LaunchCommand> {
+    let mut classpath: Vec<PathBuf> = v
+        .libraries
+        .iter()
+        .filter(|lib| lib.is_allowed())
+        .filter_map(library_path)
+        .map(|path| inst.lib_dir.join(path))
+        .collect();
+    classpath.push(client_jar(inst));
+    let classpath = classpath
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join(classpath_separator());
+
+    let game_dir = inst.game_dir();
+    let assets_root = inst.path.join(ASSETS_DIR);
+    let game_assets = if is_virtual(&v.assets) {
+        assets_root.join("virtual").join(&v.assets)
+    } else {
+        assets_root.clone()
+    };
+    let path_str = |path: &Path| path.to_string_lossy().into_owned();
+    let mut vars: HashMap<&str, String> = HashMap::from([
+        ("auth_player_name", auth.player_name.clone()),
+        ("auth_uuid", auth.uuid.clone()),
+        ("auth_access_token", auth.access_token.clone()),
+        (
+            "auth_session",
+            format!("token:{}:{}", &auth.access_token, &auth.uuid),
+        ),
+        ("auth_xuid", String::new()),
+        ("clientid", String::new()),
+        ("user_type", auth.user_type.clone()),
+        ("user_properties", "{}".to_string()),
+        ("version_name", inst.version.clone()),
+        ("version_type", String::from(v.release_type.clone())),
+        ("game_directory", path_str(&game_dir)),
+        ("assets_root", path_str(&assets_root)),
+        ("game_assets", path_str(&game_assets)),
+        ("assets_index_name", v.assets.clone()),
+        ("natives_directory", path_str(&inst.path.join(NATIVES_DIR))),
+        ("library_directory", path_str(&inst.lib_dir)),
+        ("launcher_name", LAUNCHER_NAME.to_string()),
+        ("launcher_version", env!("CARGO_PKG_VERSION").to_string()),
+        ("classpath", classpath),
+        ("classpath_separator", classpath_separator().to_string()),
+    ]);
+    let mut features = HashMap::new();
+    if let Some(resolution) = &settings.resolution {
+        vars.insert("resolution_width", resolution.width.to_string());
+        vars.insert("resolution_height", resolution.height.to_string());
+        features.insert("has_custom_resolution".to_string(), true);
+    }
+
+    let (jvm_args, game_args) = match &v.arguments {
+        Some(arguments) if !arguments.jvm.is_empty() => (
+            expand(&arguments.jvm, &features),
+            expand(&arguments.game, &features),
+        ),
+        Some(arguments) => (legacy_jvm_args(), expand(&arguments.game, &features)),
+        None => (
+            legacy_jvm_args(),
+            v.minecraft_arguments
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+        ),
+    };
+
+    let mut args: Vec<String> = Vec::new();
+    if let Some(min) = settings.min_memory {
+        args.push(format!("-Xms{}M", min));
+    }
+    if let Some(max) = settings.max_memory {
+        args.push(format!("-Xmx{}M", max));
+    }
+    args.extend(jvm_args.iter().map(|arg| substitute(arg, &vars)));
+    if let (Some(path), Some(config)) = (
+        logging_config_path(inst, v),
+        v.logging.as_ref().and_then(|l| l.client.as_ref()),
+    ) {
+        let vars = HashMap::from([("path", path.to_string_lossy().into_owned())]);
+        args.push(substitute(&config.argument, &vars));
+    }
+    args.extend(auth.jvm_args.iter().cloned());
+    args.extend(settings.jvm_args.iter().cloned());
+    args.push(v.main_class.clone());
+    args.extend(game_args.iter().map(|arg| substitute(arg, &vars)));
+    if v.arguments.is_none() {
+        if let Some(resolution) = &settings.resolution {
+            args.extend([
+                "--width".to_string(),
+                resolution.width.to_string(),
+                "--height".to_string(),
+                resolution.height.to_string(),
+            ]);
+        }
+    }
+
+    let java = java.to_path_buf();
+    let (program, args) = match settings.wrapper.split_first() {
+        Some((wrapper, wrapper_args)) => {
+            let mut all = wrapper_args.to_vec();
+            all.push(java.to_string_lossy().into_owned());
+            all.extend(args);
+            (PathBuf::from(wrapper), all)
+        }
+        None => (java, args),
+    };
+    Ok(LaunchCommand {
+        program,
+        args,
+        env: settings.env.clone(),
+        dir: game_dir,
+    })
+}
@@ -0,0 +1,225 @@
+use crate::launch::log4j::{Level, Log4jParser, LogLine};
+use crate::launch::LaunchCommand;
+use chrono::{DateTime, Local};
+use std::fs::File;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Child;
+
+pub const LATEST_LOG: &str = "latest.log";
+/// Rotated logs kept besides `latest.log`.
+const KEPT_LOGS: usize = 10;
+
+/// Moves the previous `latest.log` aside, named after when it was last
+/// written with a counter for logs from the same second, and prunes the
+/// oldest rotated logs.
+fn rotate_logs(dir: &Path) -> anyhow::Result<File> {
+    std::fs::create_dir_all(dir)?;
+    let latest = dir.join(LATEST_LOG);
+    if let Ok(meta) = std::fs::metadata(&latest) {
+        let modified: DateTime<Local> = meta.modified()?.into();
+        let stem = modified.format("%Y-%m-%d-%H%M%S").to_string();
+        let mut rotated = dir.join(format!("{}.log", stem));
+        let mut n = 1;
+        while rotated.exists() {
+            rotated = dir.join(format!("{}-{}.log", stem, n));
+            n += 1;
+        }
+        std::fs::rename(&latest, rotated)?;
+    }
+    let mut rotated: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|path| Some((std::fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    rotated.sort();
+    let excess = rotated.len().saturating_sub(KEPT_LOGS);
+    for (_, path) in rotated.into_iter().take(excess) {
+        std::fs::remove_file(path)?;
+    }
+    Ok(File::create(latest)?)
+}
+
+fn use_color() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+}
+
+fn paint(text: &str, level: Level) -> String {
+    let code = match level {
+        Level::Trace | Level::Debug => "2",
+        Level::Info => return text.to_string(),
+        Level::Warn => "33",
+        Level::Error | Level::Fatal => "31",
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// Writes game output to the terminal and the log file.
+struct Output {
+    log: File,
+    color: bool,
+}
+
+impl Output {
+    fn emit(&mut self, line: LogLine, stderr: bool) -> anyhow::Result<()> {
+        let (text, level) = match line {
+            LogLine::Record(record) => (record.to_string(), Some(record.level)),
+            LogLine::Raw(text) => (text, None),
+        };
+        writeln!(self.log, "{}", &text)?;
+        let shown = match level {
+            Some(level) if self.color => paint(&text, level),
+            _ => text,
+        };
+        if stderr {
+            eprintln!("{}", shown);
+        } else {
+            println!("{}", shown);
+        }
+        Ok(())
+    }
+}
+
+/// Runs the game, following its output until it exits. Output is logged
+/// to `latest.log` in `logs_dir`, with log4j events (see the version's
+/// `logging` config) formatted and colored by level.
+pub async fn supervise(command: &LaunchCommand, logs_dir: &Path) -> anyhow::Result<ExitStatus> {
+    let mut output = Output {
+        log: rotate_logs(logs_dir)?,
+        color: use_color(),
+    };
+    let mut child = command
+        .command()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Err(err) = follow(&mut child, &mut output).await {
+        // don't leave the game running unwatched
+        let _ = child.kill().await;
+        return Err(err);
+    }
+    Ok(child.wait().await?)
+}
+
+/// Copies the output of `child` until it closes both streams. Lines that
+/// aren't UTF-8 are shown with the invalid bytes replaced.
+async fn follow(child: &mut Child, output: &mut Output) -> anyhow::Result<()> {
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    // partial lines stay in the buffers when the other stream wins the
+    // select, so a last line without a line ending can be there at the end
+    let (mut stdout_buf, mut stderr_buf) = (Vec::new(), Vec::new());
+    let mut parser = Log4jParser::default();
+    let (mut stdout_open, mut stderr_open) = (true, true);
+    while stdout_open || stderr_open {
+        tokio::select! {
+            read = stdout.read_until(b'\n', &mut stdout_buf), if stdout_open => {
+                stdout_open = read? > 0;
+                if !stdout_buf.is_empty() {
+                    if let Some(line) = parser.feed(&take_line(&mut stdout_buf)) {
+                        output.emit(line, false)?;
+                    }
+                }
+            }
+            read = stderr.read_until(b'\n', &mut stderr_buf), if stderr_open => {
+                stderr_open = read? > 0;
+                if !stderr_buf.is_empty() {
+                    output.emit(LogLine::Raw(take_line(&mut stderr_buf)), true)?;
+                }
+            }
+        }
+    }
+    if let Some(line) = parser.finish() {
+        output.emit(line, false)?;
+    }
+    Ok(())
+}
+
+/// The line read into `buf` without its line ending, emptying `buf`.
+fn take_line(buf: &mut Vec<u8>) -> String {
+    let line = buf.strip_suffix(b"\n").unwrap_or(buf);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let line = String::from_utf8_lossy(line).into_owned();
+    buf.clear();
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("mcl-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    fn write_log(path: &Path, modified: SystemTime) {
+        let file = File::create(path).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    fn logs(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn rotated_logs_are_named_after_their_time() {
+        let dir = temp_dir("rotate-names");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_666_176_000);
+        let stem = DateTime::<Local>::from(modified)
+            .format("%Y-%m-%d-%H%M%S")
+            .to_string();
+        for _ in 0..3 {
+            drop(rotate_logs(&dir).unwrap());
+            std::fs::File::options()
+                .write(true)
+                .open(dir.join(LATEST_LOG))
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+        // the first rotation had no latest.log to move aside
+        assert_eq!(
+            logs(&dir),
+            [
+                format!("{}-1.log", stem),
+                format!("{}.log", stem),
+                LATEST_LOG.to_string()
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn oldest_rotated_logs_are_pruned() {
+        let dir = temp_dir("rotate-prune");
+        std::fs::create_dir_all(&dir).unwrap();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_666_176_000);
+        // names sorting against their age, so pruning has to go by time
+        for i in 0..KEPT_LOGS + 2 {
+            let modified = start + Duration::from_secs(60 * i as u64);
+            write_log(&dir.join(format!("{:02}.log", 99 - i)), modified);
+        }
+        write_log(&dir.join("notes.txt"), start);
+        drop(rotate_logs(&dir).unwrap());
+        let names = logs(&dir);
+        assert_eq!(names.len(), KEPT_LOGS + 2);
+        assert!(!names.contains(&"99.log".to_string()));
+        assert!(!names.contains(&"98.log".to_string()));
+        assert!(names.contains(&"97.log".to_string()));
+        assert!(names.contains(&"notes.txt".to_string()));
+        assert!(names.contains(&LATEST_LOG.to_string()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+use crate::minecraft::RawVersion;
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FabricComponent {
+    pub maven: String,
+    pub version: String,
+    #[serde(default)]
+    pub stable: bool,
+}
+
+/// An entry of `/v2/versions/loader/<game version>`: a loader build together
+/// with the intermediary mappings it runs against.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FabricLoaderVersion {
+    pub loader: FabricComponent,
+    pub intermediary: FabricComponent,
+}
+
+pub struct FabricMeta {
+    base_url: String,
+}
+
+impl FabricMeta {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub async fn loader_versions(
+        &self,
+        game_version: &str,
+    ) -> anyhow::Result<Vec<FabricLoaderVersion>> {
+        let url = format!("{}/v2/versions/loader/{}", self.base_url, game_version);
+        let res = reqwest::get(&url).await?.error_for_status()?;
+        Ok(res.json().await?)
+    }
+
+    /// Picks the requested loader version, or the newest stable one, among
+    /// the builds compatible with `game_version`.
+    pub async fn select_loader(
+        &self,
+        game_version: &str,
+        loader_version: Option<&str>,
+    ) -> anyhow::Result<FabricLoaderVersion> {
+        let versions = self.loader_versions(game_version).await?;
+        let found = match loader_version {
+            Some(wanted) => versions.into_iter().find(|v| v.loader.version == wanted),
+            None => versions.into_iter().find(|v| v.loader.stable),
+        };
+        found.ok_or_else(|| {
+            anyhow!(
+                "no fabric loader {} for minecraft {}",
+                loader_version.unwrap_or("(stable)"),
+                game_version
+            )
+        })
+    }
+
+    pub async fn profile(
+        &self,
+        game_version: &str,
+        loader_version: &str,
+    ) -> anyhow::Result<RawVersion> {
+        let url = format!(
+            "{}/v2/versions/loader/{}/{}/profile/json",
+            self.base_url, game_version, loader_version
+        );
+        let res = reqwest::get(&url).await?.error_for_status()?;
+        Ok(res.json().await?)
+    }
+}
@@ -0,0 +1,183 @@
+use crate::maven::Coordinate;
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ForgePromotions {
+    promos: HashMap<String, String>,
+}
+
+pub struct ForgeMeta {
+    maven_url: String,
+    promotions_url: String,
+}
+
+impl ForgeMeta {
+    pub fn new(maven_url: &str, promotions_url: &str) -> Self {
+        Self {
+            maven_url: maven_url.trim_end_matches('/').to_string(),
+            promotions_url: promotions_url.to_string(),
+        }
+    }
+
+    /// The recommended forge build for `game_version`, or the latest one
+    /// when none has been promoted yet.
+    pub async fn recommended(&self, game_version: &str) -> anyhow::Result<String> {
+        let res = reqwest::get(&self.promotions_url)
+            .await?
+            .error_for_status()?;
+        let promotions: ForgePromotions = res.json().await?;
+        let promos = &promotions.promos;
+        promos
+            .get(&format!("{}-recommended", game_version))
+            .or_else(|| promos.get(&format!("{}-latest", game_version)))
+            .cloned()
+            .ok_or_else(|| anyhow!("no forge build for minecraft {}", game_version))
+    }
+
+    /// The installer of a forge build, looked up in the maven metadata:
+    /// most builds are published as `<game>-<forge>`, but those of 1.7.10
+    /// and some of 1.8 and 1.9 carry a branch suffix, like
+    /// `1.7.10-10.13.4.1614-1.7.10`.
+    pub async fn installer(
+        &self,
+        game_version: &str,
+        forge_version: &str,
+    ) -> anyhow::Result<(Coordinate, String)> {
+        let url = format!(
+            "{}/net/minecraftforge/forge/maven-metadata.xml",
+            self.maven_url
+        );
+        let metadata = reqwest::get(&url).await?.error_for_status()?.text().await?;
+        let coordinate = installer_coordinate(&metadata, game_version, forge_version);
+        let url = coordinate.url(&self.maven_url);
+        Ok((coordinate, url))
+    }
+}
+
+/// The installer coordinate of a forge build given the maven metadata of
+/// forge. Builds missing from it are assumed to carry the `-<game>` suffix.
+fn installer_coordinate(metadata: &str, game_version: &str, forge_version: &str) -> Coordinate {
+    let plain = format!("{}-{}", game_version, forge_version);
+    let suffixed = format!("{}-", plain);
+    let versions: Vec<&str> = metadata
+        .split("<version>")
+        .skip(1)
+        .filter_map(|rest| Some(rest.split_once("</version>")?.0.trim()))
+        .collect();
+    let version = versions
+        .iter()
+        .find(|v| **v == plain)
+        .or_else(|| versions.iter().find(|v| v.starts_with(&suffixed)))
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| format!("{}-{}", plain, game_version));
+    Coordinate::from_str(&format!("net.minecraftforge:forge:{}:installer", version)).unwrap()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct NeoForgeVersions {
+    versions: Vec<String>,
+}
+
+pub struct NeoForgeMeta {
+    maven_url: String,
+    api_url: String,
+}
+
+impl NeoForgeMeta {
+    pub fn new(maven_url: &str, api_url: &str) -> Self {
+        Self {
+            maven_url: maven_url.trim_end_matches('/').to_string(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The newest neoforge release for `game_version`. NeoForge versions drop
+    /// the leading `1.` of the minecraft version, so 1.20.4 maps to `20.4.x`
+    /// and 1.21 to `21.0.x`.
+    pub async fn latest(&self, game_version: &str) -> anyhow::Result<String> {
+        let url = format!("{}/versions/releases/net/neoforged/neoforge", self.api_url);
+        let res = reqwest::get(&url).await?.error_for_status()?;
+        let versions: NeoForgeVersions = res.json().await?;
+
+        let mut parts = game_version.trim_start_matches("1.").split('.');
+        let prefix = format!(
+            "{}.{}.",
+            parts.next().unwrap_or_default(),
+            parts.next().unwrap_or("0")
+        );
+        versions
+            .versions
+            .into_iter()
+            .rev()
+            .find(|v| v.starts_with(&prefix))
+            .ok_or_else(|| anyhow!("no neoforge build for minecraft {}", game_version))
+    }
+
+    pub fn installer(&self, neoforge_version: &str) -> (Coordinate, String) {
+        let coordinate = Coordinate::from_str(&format!(
+            "net.neoforged:neoforge:{}:installer",
+            neoforge_version
+        ))
+        .unwrap();
+        let url = coordinate.url(&self.maven_url);
+        (coordinate, url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <groupId>net.minecraftforge</groupId>
+  <artifactId>forge</artifactId>
+  <versioning>
+    <versions>
+      <version>1.7.10-10.13.4.1614-1.7.10</version>
+      <version>1.8.9-11.15.1.2318-1.8.9</version>
+      <version>1.12.2-14.23.5.2860</version>
+      <version>1.19.2-43.1.1</version>
+    </versions>
+  </versioning>
+</metadata>
+"#;
+
+    #[test]
+    fn installer_coordinate_of_modern_builds() {
+        let coordinate = installer_coordinate(METADATA, "1.19.2", "43.1.1");
+        assert_eq!(
+            coordinate.to_string(),
+            "net.minecraftforge:forge:1.19.2-43.1.1:installer"
+        );
+        assert_eq!(
+            coordinate.url("https://maven.minecraftforge.net"),
+            "https://maven.minecraftforge.net/net/minecraftforge/forge/1.19.2-43.1.1/forge-1.19.2-43.1.1-installer.jar"
+        );
+    }
+
+    #[test]
+    fn installer_coordinate_of_suffixed_builds() {
+        let coordinate = installer_coordinate(METADATA, "1.7.10", "10.13.4.1614");
+        assert_eq!(
+            coordinate.to_string(),
+            "net.minecraftforge:forge:1.7.10-10.13.4.1614-1.7.10:installer"
+        );
+        assert_eq!(
+            coordinate.url("https://maven.minecraftforge.net"),
+            "https://maven.minecraftforge.net/net/minecraftforge/forge/1.7.10-10.13.4.1614-1.7.10/forge-1.7.10-10.13.4.1614-1.7.10-installer.jar"
+        );
+    }
+
+    #[test]
+    fn installer_coordinate_of_unlisted_builds() {
+        let coordinate = installer_coordinate(METADATA, "1.9.4", "12.17.0.2317");
+        assert_eq!(
+            coordinate.to_string(),
+            "net.minecraftforge:forge:1.9.4-12.17.0.2317-1.9.4:installer"
+        );
+    }
+}
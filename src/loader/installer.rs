@@ -0,0 +1,324 @@
+use crate::checksum::sha1_hex;
+use crate::config::Config;
+use crate::db::Installation;
+use crate::launch;
+use crate::loader::legacy::{self, LegacyInstallProfile};
+use crate::maven::Coordinate;
+use crate::minecraft::{Library, RawVersion, Resource};
+use anyhow::{anyhow, ensure};
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use zip::ZipArchive;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SidedData {
+    pub client: String,
+    pub server: String,
+}
+
+/// A step of the installer, run as `java -cp <jar>:<classpath> <Main-Class> <args>`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Processor {
+    pub jar: String,
+    #[serde(default)]
+    pub classpath: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub outputs: HashMap<String, String>,
+    pub sides: Option<Vec<String>>,
+}
+
+/// `install_profile.json` of a 1.13+ Forge or NeoForge installer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstallProfile {
+    pub version: String,
+    pub json: String,
+    pub minecraft: String,
+    #[serde(default)]
+    pub data: HashMap<String, SidedData>,
+    #[serde(default)]
+    pub processors: Vec<Processor>,
+    #[serde(default)]
+    pub libraries: Vec<Library>,
+}
+
+/// The two `install_profile.json` layouts forge installers have shipped.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum InstallerProfile {
+    Legacy(Box<LegacyInstallProfile>),
+    Modern(InstallProfile),
+}
+
+pub struct Installer {
+    path: PathBuf,
+    archive: ZipArchive<File>,
+}
+
+impl Installer {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            archive: ZipArchive::new(File::open(path)?)?,
+        })
+    }
+
+    fn read_entry(&mut self, name: &str) -> anyhow::Result<Vec<u8>> {
+        let mut entry = self.archive.by_name(name.trim_start_matches('/'))?;
+        let mut buf = Vec::new();
+        entry.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn profile(&mut self) -> anyhow::Result<InstallerProfile> {
+        Ok(serde_json::from_slice(
+            &self.read_entry("install_profile.json")?,
+        )?)
+    }
+
+    pub fn version(&mut self, profile: &InstallProfile) -> anyhow::Result<RawVersion> {
+        Ok(serde_json::from_slice(&self.read_entry(&profile.json)?)?)
+    }
+
+    /// Copies the artifacts bundled under `maven/` into `lib_dir`.
+    pub fn extract_maven(&mut self, lib_dir: &Path) -> anyhow::Result<()> {
+        for i in 0..self.archive.len() {
+            let mut entry = self.archive.by_index(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let rel = match entry
+                .enclosed_name()
+                .and_then(|p| p.strip_prefix("maven").ok())
+            {
+                Some(rel) => rel.to_path_buf(),
+                None => continue,
+            };
+            let dst = lib_dir.join(rel);
+            std::fs::create_dir_all(dst.parent().unwrap())?;
+            let mut buf = Vec::new();
+            entry.read_to_end(&mut buf)?;
+            std::fs::write(dst, buf)?;
+        }
+        Ok(())
+    }
+
+    pub fn extract_to(&mut self, name: &str, dst: &Path) -> anyhow::Result<()> {
+        let buf = self.read_entry(name)?;
+        std::fs::create_dir_all(dst.parent().unwrap())?;
+        Ok(std::fs::write(dst, buf)?)
+    }
+
+    /// Builds the token table used by processor arguments: the installer's
+    /// `data` entries for the client side plus the built-in keys.
+    fn data(
+        &mut self,
+        profile: &InstallProfile,
+        inst: &Installation,
+        data_dir: &Path,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let lib_dir = &inst.lib_dir;
+        let mut data = HashMap::new();
+        data.insert("SIDE".to_string(), "client".to_string());
+        data.insert(
+            "MINECRAFT_JAR".to_string(),
+            path_str(&lib_dir.join("client.jar")),
+        );
+        data.insert("MINECRAFT_VERSION".to_string(), profile.minecraft.clone());
+        data.insert("ROOT".to_string(), path_str(&inst.path));
+        data.insert("INSTALLER".to_string(), path_str(&self.path));
+        data.insert("LIBRARY_DIR".to_string(), path_str(lib_dir));
+
+        for (key, value) in profile.data.iter() {
+            let value = &value.client;
+            let resolved = if let Some(coord) = strip_delims(value, '[', ']') {
+                library_path(lib_dir, coord)?
+            } else if let Some(literal) = strip_delims(value, '\'', '\'') {
+                literal.to_string()
+            } else if value.starts_with('/') {
+                let dst = data_dir.join(value.trim_start_matches('/'));
+                self.extract_to(value, &dst)?;
+                path_str(&dst)
+            } else {
+                value.clone()
+            };
+            data.insert(key.clone(), resolved);
+        }
+        Ok(data)
+    }
+}
+
+impl Processor {
+    fn runs_on_client(&self) -> bool {
+        match &self.sides {
+            Some(sides) => sides.iter().any(|side| side == "client"),
+            None => true,
+        }
+    }
+
+    async fn run(
+        &self,
+        java: &Path,
+        data: &HashMap<String, String>,
+        lib_dir: &Path,
+    ) -> anyhow::Result<()> {
+        println!("=> {}", &self.jar);
+        let jar = lib_dir.join(Coordinate::from_str(&self.jar)?.path());
+        let main_class = main_class(&jar)?;
+
+        let mut classpath = vec![jar];
+        for entry in self.classpath.iter() {
+            classpath.push(lib_dir.join(Coordinate::from_str(entry)?.path()));
+        }
+        let args = self
+            .args
+            .iter()
+            .map(|arg| substitute(arg, data, lib_dir))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let status = tokio::process::Command::new(java)
+            .arg("-cp")
+            .arg(std::env::join_paths(classpath)?)
+            .arg(&main_class)
+            .args(&args)
+            .status()
+            .await?;
+        ensure!(
+            status.success(),
+            "processor {} failed ({})",
+            &self.jar,
+            status
+        );
+
+        for (file, sha1) in self.outputs.iter() {
+            let file = substitute(file, data, lib_dir)?;
+            let sha1 = substitute(sha1, data, lib_dir)?;
+            let actual = sha1_hex(&std::fs::read(&file)?);
+            ensure!(
+                actual == sha1,
+                "processor {} produced {} with sha1 {}, expected {}",
+                &self.jar,
+                file,
+                actual,
+                sha1
+            );
+        }
+        Ok(())
+    }
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn strip_delims(s: &str, open: char, close: char) -> Option<&str> {
+    s.strip_prefix(open).and_then(|s| s.strip_suffix(close))
+}
+
+fn library_path(lib_dir: &Path, coord: &str) -> anyhow::Result<String> {
+    Ok(path_str(&lib_dir.join(Coordinate::from_str(coord)?.path())))
+}
+
+/// Expands `[maven coordinate]` arguments to library paths and `{KEY}`
+/// tokens to their installer data value.
+fn substitute(arg: &str, data: &HashMap<String, String>, lib_dir: &Path) -> anyhow::Result<String> {
+    if let Some(coord) = strip_delims(arg, '[', ']') {
+        return library_path(lib_dir, coord);
+    }
+    let mut out = String::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unterminated token in processor argument '{}'", arg))?
+            + start;
+        let key = &rest[start + 1..end];
+        let value = data
+            .get(key)
+            .ok_or_else(|| anyhow!("unknown installer data key '{}'", key))?;
+        out.push_str(&rest[..start]);
+        out.push_str(value);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn main_class(jar: &Path) -> anyhow::Result<String> {
+    let mut archive = ZipArchive::new(File::open(jar)?)?;
+    let manifest = BufReader::new(archive.by_name("META-INF/MANIFEST.MF")?);
+    for line in manifest.lines() {
+        if let Some(class) = line?.strip_prefix("Main-Class:") {
+            return Ok(class.trim().to_string());
+        }
+    }
+    Err(anyhow!("{:?} has no Main-Class", jar))
+}
+
+/// Runs a downloaded Forge/NeoForge installer against `inst`: fetches the
+/// installer's libraries, runs its client processors (or, for legacy
+/// installers, extracts the universal jar) and returns the version JSON to
+/// register.
+pub async fn run(
+    config: &Config,
+    inst: &Installation,
+    installer_path: &Path,
+) -> anyhow::Result<RawVersion> {
+    let mut installer = Installer::open(installer_path)?;
+    match installer.profile()? {
+        InstallerProfile::Legacy(profile) => {
+            legacy::run(config, inst, &mut installer, *profile).await
+        }
+        InstallerProfile::Modern(profile) => {
+            run_modern(config, inst, &mut installer, profile).await
+        }
+    }
+}
+
+async fn run_modern(
+    config: &Config,
+    inst: &Installation,
+    installer: &mut Installer,
+    profile: InstallProfile,
+) -> anyhow::Result<RawVersion> {
+    ensure!(
+        profile.minecraft == inst.version,
+        "installer is for minecraft {} but the installation runs {}",
+        profile.minecraft,
+        inst.version
+    );
+    let version = installer.version(&profile)?;
+
+    println!("Installing installer libraries");
+    installer.extract_maven(&inst.lib_dir)?;
+    let resources: Vec<Resource> = profile
+        .libraries
+        .iter()
+        .map(|lib| Resource::from(lib.clone()))
+        .collect();
+    try_join_all(resources.iter().map(|lib| lib.install_to(&inst.lib_dir))).await?;
+
+    // processors run on the Java the game would, Forge's tools target the
+    // game's Java version
+    let java = launch::java_for(
+        config,
+        &inst.settings.or(&config.launch),
+        &inst.load_version()?,
+    )
+    .await?;
+    println!("Running installer processors");
+    let data_dir = inst.path.join("installer-data");
+    let data = installer.data(&profile, inst, &data_dir)?;
+    for processor in profile.processors.iter().filter(|p| p.runs_on_client()) {
+        processor.run(&java, &data, &inst.lib_dir).await?;
+    }
+    std::fs::remove_dir_all(&data_dir).ok();
+
+    Ok(version)
+}
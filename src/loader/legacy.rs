@@ -0,0 +1,158 @@
+use crate::config::Config;
+use crate::db::Installation;
+use crate::java;
+use crate::launch;
+use crate::loader::installer::Installer;
+use crate::maven::Coordinate;
+use crate::minecraft::{Artifact, Fetch, JavaVersion, RawVersion};
+use anyhow::{anyhow, ensure};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LegacyInstall {
+    /// Maven coordinate the universal jar is installed as.
+    pub path: String,
+
+    /// Name of the universal jar inside the installer.
+    #[serde(rename = "filePath")]
+    pub file_path: String,
+    pub minecraft: String,
+}
+
+/// `install_profile.json` of a pre-1.13 forge installer, which embeds the
+/// version JSON and a universal jar instead of running processors.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LegacyInstallProfile {
+    pub install: LegacyInstall,
+
+    #[serde(rename = "versionInfo")]
+    pub version_info: RawVersion,
+}
+
+pub async fn run(
+    config: &Config,
+    inst: &Installation,
+    installer: &mut Installer,
+    profile: LegacyInstallProfile,
+) -> anyhow::Result<RawVersion> {
+    let install = &profile.install;
+    ensure!(
+        install.minecraft == inst.version,
+        "installer is for minecraft {} but the installation runs {}",
+        install.minecraft,
+        inst.version
+    );
+
+    println!("Extracting {}", &install.file_path);
+    let universal = inst
+        .lib_dir
+        .join(Coordinate::from_str(&install.path)?.path());
+    installer.extract_to(&install.file_path, &universal)?;
+
+    // Some of these libraries only exist on forge's maven as `.pack.xz`, so
+    // they are fetched here rather than by the generic library install.
+    println!("Installing forge libraries");
+    let mut unpack200 = None;
+    for lib in profile.version_info.libraries.iter() {
+        let base = match (&lib.url, &lib.downloads) {
+            (Some(base), None) if lib.is_allowed() => base,
+            _ => continue,
+        };
+        let coordinate = lib.coordinate()?;
+        let dst = inst.lib_dir.join(coordinate.path());
+        if dst.exists() {
+            continue;
+        }
+        println!("=> {}", &lib.name);
+        let url = coordinate.url(base);
+        let buf = match fetch(&url).await {
+            Ok(buf) => buf,
+            Err(_) => {
+                let packed = fetch(&format!("{}.pack.xz", url)).await?;
+                if unpack200.is_none() {
+                    unpack200 = Some(find_unpack200(config, inst).await?);
+                }
+                unpack(unpack200.as_deref().unwrap(), &packed, &dst)?
+            }
+        };
+        std::fs::create_dir_all(dst.parent().unwrap())?;
+        std::fs::write(dst, buf)?;
+    }
+
+    let mut version = profile.version_info;
+    if version.inherits_from.is_none() {
+        version.inherits_from = Some(install.minecraft.clone());
+    }
+    Ok(version)
+}
+
+async fn fetch(url: &str) -> anyhow::Result<Bytes> {
+    Artifact {
+        sha1: None,
+        size: 0,
+        url: url.to_string(),
+        path: None,
+    }
+    .fetch_async()
+    .await
+}
+
+/// `unpack200` was dropped in Java 14, so it's taken from the Java 8 the
+/// installation runs on, or else from Mojang's legacy runtime.
+async fn find_unpack200(config: &Config, inst: &Installation) -> anyhow::Result<PathBuf> {
+    let next_to = |java: PathBuf| {
+        // system java is usually a symlink into the actual JDK
+        let java = std::fs::canonicalize(&java).unwrap_or(java);
+        java.with_file_name(if cfg!(windows) {
+            "unpack200.exe"
+        } else {
+            "unpack200"
+        })
+    };
+    let settings = inst.settings.or(&config.launch);
+    let unpack200 = next_to(launch::java_for(config, &settings, &inst.load_version()?).await?);
+    if unpack200.exists() {
+        return Ok(unpack200);
+    }
+    let unpack200 = next_to(java::ensure_runtime(config, &JavaVersion::default()).await?);
+    ensure!(
+        unpack200.exists(),
+        "no unpack200 found in the Java 8 runtime ({:?})",
+        unpack200
+    );
+    Ok(unpack200)
+}
+
+/// Turns a forge `.pack.xz` into a jar: xz-decompresses it, drops the
+/// trailing checksum block and runs the result through `unpack200`.
+fn unpack(unpack200: &Path, packed: &[u8], dst: &Path) -> anyhow::Result<Bytes> {
+    let mut data = Vec::new();
+    lzma_rs::xz_decompress(&mut Cursor::new(packed), &mut data)
+        .map_err(|err| anyhow!("invalid .pack.xz ({:?})", err))?;
+
+    // <pack><checksums><checksums length, u32 LE>"SIGN"
+    if data.ends_with(b"SIGN") && data.len() >= 8 {
+        let n = data.len();
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&data[n - 8..n - 4]);
+        let len = u32::from_le_bytes(len) as usize;
+        ensure!(len + 8 <= n, "corrupt .pack.xz signature block");
+        data.truncate(n - 8 - len);
+    }
+
+    let pack = dst.with_extension("pack");
+    std::fs::create_dir_all(dst.parent().unwrap())?;
+    std::fs::write(&pack, &data)?;
+    let status = std::process::Command::new(unpack200)
+        .arg(&pack)
+        .arg(dst)
+        .status()
+        .map_err(|err| anyhow!("could not run {:?} for {:?} ({})", unpack200, dst, err));
+    std::fs::remove_file(&pack).ok();
+    ensure!(status?.success(), "unpack200 failed for {:?}", dst);
+    Ok(Bytes::from(std::fs::read(dst)?))
+}
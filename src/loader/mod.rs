@@ -0,0 +1,222 @@
+mod fabric;
+mod forge;
+mod installer;
+mod legacy;
+mod quilt;
+
+pub use fabric::*;
+pub use forge::*;
+pub use quilt::*;
+
+use crate::config::Config;
+use crate::db::Installation;
+use crate::maven::Coordinate;
+use crate::minecraft::{Artifact, RawVersion, Resource};
+use anyhow::ensure;
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct UnknownLoaderError(String);
+
+impl fmt::Display for UnknownLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown mod loader '{}'", self.0)
+    }
+}
+
+impl std::error::Error for UnknownLoaderError {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum LoaderKind {
+    #[serde(rename = "fabric")]
+    Fabric,
+    #[serde(rename = "quilt")]
+    Quilt,
+    #[serde(rename = "forge")]
+    Forge,
+    #[serde(rename = "neoforge")]
+    NeoForge,
+}
+
+impl fmt::Display for LoaderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoaderKind::Fabric => write!(f, "fabric"),
+            LoaderKind::Quilt => write!(f, "quilt"),
+            LoaderKind::Forge => write!(f, "forge"),
+            LoaderKind::NeoForge => write!(f, "neoforge"),
+        }
+    }
+}
+
+impl FromStr for LoaderKind {
+    type Err = UnknownLoaderError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fabric" => Ok(LoaderKind::Fabric),
+            "quilt" => Ok(LoaderKind::Quilt),
+            "forge" => Ok(LoaderKind::Forge),
+            "neoforge" => Ok(LoaderKind::NeoForge),
+            _ => Err(UnknownLoaderError(s.to_string())),
+        }
+    }
+}
+
+/// A `<loader>[@<version>]` argument, e.g. `fabric@0.14.9`.
+#[derive(Debug, Clone)]
+pub struct LoaderSpec {
+    pub kind: LoaderKind,
+    pub version: Option<String>,
+}
+
+impl FromStr for LoaderSpec {
+    type Err = UnknownLoaderError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, version) = match s.split_once('@') {
+            Some((kind, version)) => (kind, Some(version.to_string())),
+            None => (s, None),
+        };
+        Ok(Self {
+            kind: LoaderKind::from_str(kind)?,
+            version,
+        })
+    }
+}
+
+/// Installs `spec` on top of the vanilla version already present in `inst`
+/// and makes the loader profile the installation's version.
+pub async fn install(
+    config: &Config,
+    inst: &mut Installation,
+    spec: &LoaderSpec,
+) -> anyhow::Result<()> {
+    let game_version = inst.version.clone();
+    let (loader_version, profile) = match spec.kind {
+        LoaderKind::Fabric => {
+            let meta = FabricMeta::new(&config.fabric_meta_url);
+            println!("Fetching fabric loader versions...");
+            let selected = meta
+                .select_loader(&game_version, spec.version.as_deref())
+                .await?;
+            println!(
+                "Using fabric loader {} (intermediary {})",
+                selected.loader.version, selected.intermediary.version
+            );
+            let profile = meta
+                .profile(&game_version, &selected.loader.version)
+                .await?;
+            (selected.loader.version, profile)
+        }
+        LoaderKind::Quilt => {
+            let meta = QuiltMeta::new(&config.quilt_meta_url);
+            println!("Fetching quilt loader versions...");
+            let selected = meta
+                .select_loader(&game_version, spec.version.as_deref())
+                .await?;
+            println!("Using quilt loader {}", selected.loader.version);
+            let profile = meta
+                .profile(&game_version, &selected.loader.version)
+                .await?;
+            (selected.loader.version, profile)
+        }
+        LoaderKind::Forge => {
+            let meta = ForgeMeta::new(&config.forge_maven_url, &config.forge_promotions_url);
+            let forge_version = match &spec.version {
+                Some(version) => version
+                    .trim_start_matches(&format!("{}-", game_version))
+                    .to_string(),
+                None => {
+                    println!("Fetching forge promotions...");
+                    meta.recommended(&game_version).await?
+                }
+            };
+            println!("Using forge {}", forge_version);
+            let (coordinate, url) = meta.installer(&game_version, &forge_version).await?;
+            let profile = run_installer(config, inst, coordinate, url).await?;
+            (forge_version, profile)
+        }
+        LoaderKind::NeoForge => {
+            let meta = NeoForgeMeta::new(&config.neoforge_maven_url, &config.neoforge_api_url);
+            let neoforge_version = match &spec.version {
+                Some(version) => version.clone(),
+                None => {
+                    println!("Fetching neoforge versions...");
+                    meta.latest(&game_version).await?
+                }
+            };
+            println!("Using neoforge {}", neoforge_version);
+            let (coordinate, url) = meta.installer(&neoforge_version);
+            let profile = run_installer(config, inst, coordinate, url).await?;
+            (neoforge_version, profile)
+        }
+    };
+    install_profile(inst, profile).await?;
+    inst.loader = Some(spec.kind.clone());
+    inst.loader_version = Some(loader_version);
+    Ok(())
+}
+
+/// Downloads a Forge-style installer jar into the installation's libraries
+/// and runs it headlessly.
+async fn run_installer(
+    config: &Config,
+    inst: &Installation,
+    coordinate: Coordinate,
+    url: String,
+) -> anyhow::Result<RawVersion> {
+    println!("Downloading installer");
+    let sha1 = installer_sha1(&url).await?;
+    let resource = Resource {
+        name: coordinate.to_string(),
+        artifacts: vec![Artifact {
+            sha1: Some(sha1),
+            size: 0,
+            url,
+            path: Some(coordinate.path()),
+        }],
+    };
+    resource.install_to(&inst.lib_dir).await?;
+    installer::run(config, inst, &inst.lib_dir.join(coordinate.path())).await
+}
+
+/// The checksum maven publishes next to the installer, which gets executed
+/// and so is never run unverified.
+async fn installer_sha1(url: &str) -> anyhow::Result<String> {
+    let res = reqwest::get(format!("{}.sha1", url))
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    // some repositories append the file name after the hash
+    let sha1 = res.split_whitespace().next().unwrap_or_default();
+    ensure!(
+        sha1.len() == 40 && sha1.chars().all(|c| c.is_ascii_hexdigit()),
+        "{}.sha1 is not a sha1 checksum",
+        url
+    );
+    Ok(sha1.to_ascii_lowercase())
+}
+
+/// Stores an `inheritsFrom` child profile next to its parent and downloads
+/// the libraries it adds.
+pub async fn install_profile(inst: &mut Installation, profile: RawVersion) -> anyhow::Result<()> {
+    std::fs::write(
+        inst.path.join(format!("{}.json", profile.id)),
+        serde_json::to_vec(&profile)?.as_slice(),
+    )?;
+
+    println!("Installing loader libraries");
+    let resources: Vec<Resource> = profile
+        .libraries
+        .iter()
+        .filter(|lib| lib.is_allowed())
+        .map(|lib| Resource::from(lib.clone()))
+        .collect();
+    try_join_all(resources.iter().map(|lib| lib.install_to(&inst.lib_dir))).await?;
+
+    inst.version = profile.id;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use crate::minecraft::RawVersion;
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuiltComponent {
+    pub maven: String,
+    pub version: String,
+}
+
+/// An entry of `/v3/versions/loader/<game version>`. Quilt runs on hashed
+/// mojmap rather than intermediary, but the meta still reports both.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuiltLoaderVersion {
+    pub loader: QuiltComponent,
+    pub hashed: Option<QuiltComponent>,
+    pub intermediary: Option<QuiltComponent>,
+}
+
+impl QuiltLoaderVersion {
+    /// Quilt's meta has no stability flag; pre-releases carry a suffix
+    /// such as `-beta.3`.
+    pub fn is_stable(&self) -> bool {
+        !self.loader.version.contains('-')
+    }
+}
+
+pub struct QuiltMeta {
+    base_url: String,
+}
+
+impl QuiltMeta {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub async fn loader_versions(
+        &self,
+        game_version: &str,
+    ) -> anyhow::Result<Vec<QuiltLoaderVersion>> {
+        let url = format!("{}/v3/versions/loader/{}", self.base_url, game_version);
+        let res = reqwest::get(&url).await?.error_for_status()?;
+        Ok(res.json().await?)
+    }
+
+    /// Picks the requested loader version, or the newest stable one, among
+    /// the builds compatible with `game_version`.
+    pub async fn select_loader(
+        &self,
+        game_version: &str,
+        loader_version: Option<&str>,
+    ) -> anyhow::Result<QuiltLoaderVersion> {
+        let versions = self.loader_versions(game_version).await?;
+        let found = match loader_version {
+            Some(wanted) => versions.into_iter().find(|v| v.loader.version == wanted),
+            None => versions.into_iter().find(|v| v.is_stable()),
+        };
+        found.ok_or_else(|| {
+            anyhow!(
+                "no quilt loader {} for minecraft {}",
+                loader_version.unwrap_or("(stable)"),
+                game_version
+            )
+        })
+    }
+
+    pub async fn profile(
+        &self,
+        game_version: &str,
+        loader_version: &str,
+    ) -> anyhow::Result<RawVersion> {
+        let url = format!(
+            "{}/v3/versions/loader/{}/{}/profile/json",
+            self.base_url, game_version, loader_version
+        );
+        let res = reqwest::get(&url).await?.error_for_status()?;
+        Ok(res.json().await?)
+    }
+}
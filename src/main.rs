@@ -1,23 +1,42 @@
 #![forbid(unsafe_code)]
 
-use std::cell::RefCell;
 use crate::minecraft::{Fetch, Resources, VersionInfo};
 use scopeguard::defer;
+use std::cell::RefCell;
 
+mod auth;
 mod cache;
 mod checksum;
 mod config;
 mod db;
+mod import;
 mod install_operation;
+mod java;
+mod launch;
+mod loader;
+mod maven;
 mod minecraft;
+mod modpack;
+mod modrinth;
+mod mods;
 
-use crate::db::{Installation, JsonFileDb};
-use clap::{App, Arg, SubCommand};
+use crate::db::{Installation, JsonFileDb, LAUNCH_SETTING_KEYS};
+use crate::import::{PrismInstance, VanillaLauncher};
+use crate::loader::LoaderSpec;
+use crate::minecraft::{Version, VersionManifest};
+use crate::modpack::Modpack;
+use crate::modrinth::Modrinth;
+use crate::mods::{Lockfile, ModRequest, ModTarget};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use config::Config;
 use futures::future::try_join_all;
+use std::path::Path;
 
-fn prepare_config() -> anyhow::Result<Config> {
-    let config = Config::new();
+fn prepare_config(matches: &ArgMatches) -> anyhow::Result<Config> {
+    let mut config = Config::load()?;
+    if let Some(db_path) = matches.get_one::<String>("db_path") {
+        config.db_path = db_path.into();
+    }
     config.prepare_dirs()?;
     Ok(config)
 }
@@ -31,6 +50,7 @@ fn build_cli() -> App<'static> {
             Arg::with_name("db_path")
                 .short('d')
                 .long("db-path")
+                .takes_value(true)
                 .help("override default database path"),
         )
         .subcommand(
@@ -41,15 +61,331 @@ fn build_cli() -> App<'static> {
                         .required(true)
                         .help("version name (e.g '1.12')")
                         .index(1),
+                )
+                .arg(
+                    Arg::with_name("loader")
+                        .long("loader")
+                        .takes_value(true)
+                        .help("mod loader to install on top, as <loader>[@<version>] (e.g 'fabric@0.14.9', 'quilt', 'forge@43.1.1', 'neoforge')"),
                 ),
         )
         .subcommand(SubCommand::with_name("list").about("lists installed versions"))
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("creates a new installation from a modpack or another launcher")
+                .arg(
+                    Arg::with_name("file")
+                        .required_unless_present("from")
+                        .help("path to a Modrinth .mrpack, a CurseForge modpack zip, or the instance directory for --from (defaults to the official launcher's directory)")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .takes_value(true)
+                        .possible_values(["prism", "vanilla"])
+                        .help("import from another launcher (MultiMC/Prism, or the profiles of the official launcher)"),
+                )
+                .arg(
+                    Arg::with_name("link")
+                        .long("link")
+                        .requires("from")
+                        .help("symlink the instance's game files instead of copying them"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("exports an installation as a modpack")
+                .arg(
+                    Arg::with_name("name")
+                        .required(true)
+                        .help("installation name")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(["mrpack"])
+                        .default_value("mrpack")
+                        .help("modpack format"),
+                )
+                .arg(
+                    Arg::with_name("pack_version")
+                        .long("pack-version")
+                        .takes_value(true)
+                        .default_value("1.0.0")
+                        .help("version of the exported pack"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short('o')
+                        .long("output")
+                        .takes_value(true)
+                        .help("output file (defaults to <name>.mrpack)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("mod")
+                .about("manages the mods of an installation")
+                .subcommand_required(true)
+                .subcommand(
+                    SubCommand::with_name("search")
+                        .about("searches Modrinth for mods the installation can run")
+                        .arg(installation_arg())
+                        .arg(
+                            Arg::with_name("query")
+                                .required(true)
+                                .help("search terms")
+                                .index(2),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("installs a mod from Modrinth, with its dependencies")
+                        .arg(installation_arg())
+                        .arg(project_arg().help(
+                            "Modrinth project id or slug, optionally constrained as <project>@<version> ('=1.2.3' pins, '1.2' allows 1.2.x)",
+                        )),
+                )
+                .subcommand(
+                    SubCommand::with_name("update")
+                        .about("updates mods within their version constraints")
+                        .arg(installation_arg())
+                        .arg(project_arg().required(false)),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("uninstalls a mod")
+                        .arg(installation_arg())
+                        .arg(project_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("lists installed mods, including jars added by hand")
+                        .arg(installation_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about("checks the mod jars for missing dependencies and incompatibilities")
+                        .arg(installation_arg()),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("java")
+                .about("manages Java runtimes")
+                .subcommand_required(true)
+                .subcommand(
+                    SubCommand::with_name("install")
+                        .about("installs the Mojang Java runtime an installation needs")
+                        .arg(installation_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("lists the Java installations found on this machine")
+                        .arg(
+                            Arg::with_name("for")
+                                .long("for")
+                                .takes_value(true)
+                                .help("only show the Java an installation would run on"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sync")
+                .about("makes the mods of an installation match its lockfile")
+                .arg(installation_arg())
+                .arg(
+                    Arg::with_name("lockfile")
+                        .long("lockfile")
+                        .takes_value(true)
+                        .help("lockfile to apply instead, e.g. the mods.lock of another machine's game directory"),
+                )
+                .arg(
+                    Arg::with_name("allow_empty")
+                        .long("allow-empty")
+                        .help("sync even when no mods are locked, removing every jar in mods/"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("instance")
+                .about("manages the launch settings of an installation")
+                .subcommand_required(true)
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("changes a launch setting, or resets it to the config default when no value is given")
+                        .arg(installation_arg())
+                        .arg(
+                            Arg::with_name("key")
+                                .required(true)
+                                .possible_values(LAUNCH_SETTING_KEYS)
+                                .help("setting to change")
+                                .index(2),
+                        )
+                        .arg(
+                            Arg::with_name("value")
+                                .help("new value: a path for java, MiB (or e.g. '4G') for memory, arguments for jvm-args and wrapper (one quoted line, or one argument per value), NAME=value for env, WIDTHxHEIGHT for resolution")
+                                .allow_hyphen_values(true)
+                                .multiple_values(true)
+                                .index(3),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("show")
+                        .about("shows the launch settings of an installation, with config defaults applied")
+                        .arg(installation_arg()),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("account")
+                .about("manages Minecraft accounts")
+                .subcommand_required(true)
+                .subcommand(
+                    SubCommand::with_name("login")
+                        .about("signs in with a Microsoft account"),
+                )
+                .subcommand(
+                    SubCommand::with_name("add-yggdrasil")
+                        .about("signs in to a Yggdrasil compatible authentication server, used through authlib-injector")
+                        .arg(
+                            Arg::with_name("server")
+                                .required(true)
+                                .help("URL of the authentication server (its API root, or a site pointing at it)")
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::with_name("username")
+                                .required(true)
+                                .help("user name or email on that server")
+                                .index(2),
+                        )
+                        .arg(
+                            Arg::with_name("password_stdin")
+                                .long("password-stdin")
+                                .help("read the password from standard input instead of prompting"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("add-offline")
+                        .about("adds an offline account, for LAN games and testing")
+                        .arg(
+                            Arg::with_name("player")
+                                .required(true)
+                                .help("player name")
+                                .index(1),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("list").about("lists the accounts signed in"))
+                .subcommand(
+                    SubCommand::with_name("use")
+                        .about("makes an account the one launched with by default")
+                        .arg(account_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("forgets an account")
+                        .arg(account_arg()),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("skin")
+                .about("changes the skin of a Microsoft account")
+                .subcommand_required(true)
+                .arg(account_option())
+                .subcommand(
+                    SubCommand::with_name("upload")
+                        .about("uploads a 64x64 (or 64x32) PNG as the skin")
+                        .arg(
+                            Arg::with_name("png")
+                                .required(true)
+                                .help("path to the skin")
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::with_name("model")
+                                .long("model")
+                                .takes_value(true)
+                                .possible_values(["classic", "slim"])
+                                .default_value("classic")
+                                .help("arm width of the skin"),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("reset").about("goes back to the default skin")),
+        )
+        .subcommand(
+            SubCommand::with_name("cape")
+                .about("manages the capes of a Microsoft account")
+                .subcommand_required(true)
+                .arg(account_option())
+                .subcommand(SubCommand::with_name("list").about("lists the capes the account owns"))
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("shows one of the account's capes")
+                        .arg(
+                            Arg::with_name("cape")
+                                .required(true)
+                                .help("cape id or name")
+                                .index(1),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("launch")
+                .about("starts an installation")
+                .arg(installation_arg())
+                .arg(account_option().help("account to play with (defaults to the one picked with `account use`)"))
+                .arg(
+                    Arg::with_name("print")
+                        .long("print")
+                        .help("print the command instead of running it"),
+                ),
+        )
+}
+
+fn installation_arg() -> Arg<'static> {
+    Arg::with_name("name")
+        .required(true)
+        .help("installation name")
+        .index(1)
+}
+
+fn account_arg() -> Arg<'static> {
+    Arg::with_name("account")
+        .required(true)
+        .help("player name or UUID")
+        .index(1)
+}
+
+fn account_option() -> Arg<'static> {
+    Arg::with_name("account")
+        .long("account")
+        .takes_value(true)
+        .global(true)
+        .help("account to use (defaults to the one picked with `account use`)")
+}
+
+fn project_arg() -> Arg<'static> {
+    Arg::with_name("project")
+        .required(true)
+        .help("Modrinth project id or slug")
+        .index(2)
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let config = prepare_config()?;
-    let db = RefCell::new(JsonFileDb::open(&config.db_path)?);
+    let exit_code = run().await?;
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
+
+/// Runs the command line, returning the exit code of a launched game.
+async fn run() -> anyhow::Result<i32> {
+    let mut exit_code = 0;
+    let matches = build_cli().get_matches();
+    let config = prepare_config(&matches)?;
+    let db = RefCell::new(JsonFileDb::open(&config.db_file())?);
     println!("I will look for cached data in {:?}", &config.cache_path);
 
     let manifest = cache::read_manifest(&config).await?;
@@ -58,33 +394,541 @@ async fn main() -> anyhow::Result<()> {
         db.borrow_mut().commit().unwrap_or_else(|err| panic!("error committing database ({})", err))
     }
 
-    let matches = build_cli().get_matches();
     match matches.subcommand() {
         Some(("install", install_matches)) => {
             let version_str = install_matches.get_one::<String>("version").unwrap();
-            let version_info = manifest
-                .versions
-                .iter()
-                .find(|info| &info.id == version_str);
-            if let Some(vinfo) = version_info {
-                db.borrow_mut().db.installations.push(install(&config, vinfo).await?);
+            let loader = install_matches
+                .get_one::<String>("loader")
+                .map(|s| s.parse::<LoaderSpec>())
+                .transpose()?;
+            let inst =
+                install_with_loader(&config, &manifest, version_str, loader.as_ref()).await?;
+            db.borrow_mut().db.installations.push(inst);
+        }
+        Some(("list", _)) => {
+            for inst in db.borrow().db.installations.iter() {
+                match (&inst.loader, &inst.loader_version) {
+                    (Some(loader), Some(loader_version)) => println!(
+                        "{}\t{}\t{} {}",
+                        inst.name, inst.version, loader, loader_version
+                    ),
+                    _ => println!("{}\t{}", inst.name, inst.version),
+                }
+            }
+        }
+        Some(("import", import_matches)) => {
+            let file = import_matches.get_one::<String>("file").map(Path::new);
+            let required_file = || file.ok_or_else(|| anyhow::anyhow!("no file to import given"));
+            let insts = match import_matches.get_one::<String>("from").map(String::as_str) {
+                Some("vanilla") => {
+                    let root = file
+                        .map(Path::to_path_buf)
+                        .unwrap_or_else(VanillaLauncher::default_dir);
+                    import_vanilla(&config, &manifest, &root).await?
+                }
+                Some("prism") => {
+                    let link = import_matches.contains_id("link");
+                    vec![import_prism(&config, &manifest, required_file()?, link).await?]
+                }
+                _ => vec![import_modpack(&config, &manifest, required_file()?).await?],
+            };
+            for inst in insts {
+                println!("Imported as {}", &inst.name);
+                db.borrow_mut().db.installations.push(inst);
+            }
+        }
+        Some(("export", export_matches)) => {
+            let name = export_matches.get_one::<String>("name").unwrap();
+            let pack_version = export_matches.get_one::<String>("pack_version").unwrap();
+            let output = export_matches
+                .get_one::<String>("output")
+                .cloned()
+                .unwrap_or_else(|| format!("{}.mrpack", name));
+            let inst = db.borrow().db.installation(name)?.clone();
+            let modrinth = Modrinth::new(&config.modrinth_api_url);
+            modpack::export(&inst, &modrinth, pack_version, Path::new(&output)).await?;
+            println!("Exported {} to {}", name, output);
+        }
+        Some(("mod", mod_matches)) => {
+            let (command, sub_matches) = mod_matches.subcommand().unwrap();
+            let name = sub_matches.get_one::<String>("name").unwrap();
+            let inst = db.borrow().db.installation(name)?.clone();
+            manage_mods(&config, &inst, command, sub_matches).await?;
+        }
+        Some(("java", java_matches)) => match java_matches.subcommand() {
+            Some(("install", install_matches)) => {
+                let name = install_matches.get_one::<String>("name").unwrap();
+                let inst = db.borrow().db.installation(name)?.clone();
+                let v = inst.load_version()?;
+                let java = java::ensure_runtime(&config, &v.java_version).await?;
+                println!("Java {} is at {:?}", v.java_version.major_version, java);
+            }
+            Some(("list", list_matches)) => {
+                let installs = match list_matches.get_one::<String>("for") {
+                    Some(name) => {
+                        let inst = db.borrow().db.installation(name)?.clone();
+                        let v = inst.load_version()?;
+                        let install =
+                            java::find_java(&config, &v.java_version)?.ok_or_else(|| {
+                                anyhow::anyhow!(
+                                    "no Java {} found, try `mcl java install {}`",
+                                    v.java_version.major_version,
+                                    name
+                                )
+                            })?;
+                        vec![install]
+                    }
+                    None => java::detect(&config)?,
+                };
+                for install in installs {
+                    println!(
+                        "{}\t{}\t{}\t{}\t{}",
+                        install.major_version,
+                        install.version,
+                        install.vendor,
+                        install.arch,
+                        install.path.display()
+                    );
+                }
+            }
+            _ => unreachable!("Subcommands are required!"),
+        },
+        Some(("sync", sync_matches)) => {
+            let name = sync_matches.get_one::<String>("name").unwrap();
+            let inst = db.borrow().db.installation(name)?.clone();
+            let lock = match sync_matches.get_one::<String>("lockfile") {
+                Some(path) => Lockfile::open(Path::new(path))?,
+                None => Lockfile::load(&inst)?,
+            };
+            if lock.mods.is_empty() && !sync_matches.contains_id("allow_empty") {
+                // installations imported from packs or other launchers have
+                // mods but no lockfile
+                anyhow::bail!(
+                    "no mods are locked for {}, syncing would remove every jar in its mods directory (pass --allow-empty to do it anyway)",
+                    name
+                );
+            }
+            lock.check_target(&ModTarget::of(&inst)?)?;
+            mods::sync(&inst, &lock).await?;
+            lock.save(&inst)?;
+            println!("{} mods in sync", lock.mods.len());
+        }
+        Some(("instance", instance_matches)) => match instance_matches.subcommand() {
+            Some(("set", set_matches)) => {
+                let name = set_matches.get_one::<String>("name").unwrap();
+                let key = set_matches.get_one::<String>("key").unwrap();
+                let values: Vec<String> = set_matches
+                    .get_many::<String>("value")
+                    .map(|values| values.cloned().collect())
+                    .unwrap_or_default();
+                db.borrow_mut()
+                    .db
+                    .installation_mut(name)?
+                    .settings
+                    .set(key, &values)?;
+            }
+            Some(("show", show_matches)) => {
+                let name = show_matches.get_one::<String>("name").unwrap();
+                let inst = db.borrow().db.installation(name)?.clone();
+                let settings = inst.settings.or(&config.launch);
+                println!("{}", serde_json::to_string_pretty(&settings)?);
+            }
+            _ => unreachable!("Subcommands are required!"),
+        },
+        Some(("account", account_matches)) => {
+            let mut accounts = auth::AccountStore::open(&config)?;
+            match account_matches.subcommand() {
+                Some(("login", _)) => {
+                    let auth = auth::MicrosoftAuth::new(&config)?;
+                    let code = auth.device_code().await?;
+                    match &code.message {
+                        Some(message) => println!("{}", message),
+                        None => println!(
+                            "To sign in, open {} and enter the code {}",
+                            code.verification_uri, code.user_code
+                        ),
+                    }
+                    let msa = auth.poll_token(&code).await?;
+                    let login = auth.minecraft_login(&msa).await?;
+                    println!("Logged in as {} ({})", login.profile.name, login.profile.id);
+                    accounts.add(login.into());
+                }
+                Some(("add-yggdrasil", add_matches)) => {
+                    let server = add_matches.get_one::<String>("server").unwrap();
+                    let username = add_matches.get_one::<String>("username").unwrap();
+                    let password = if add_matches.contains_id("password_stdin") {
+                        let mut line = String::new();
+                        std::io::stdin().read_line(&mut line)?;
+                        line.trim_end_matches(['\r', '\n']).to_string()
+                    } else {
+                        rpassword::prompt_password("Password: ")?
+                    };
+                    let server = auth::Yggdrasil::discover(server).await?;
+                    let login = server.authenticate(username, &password).await?;
+                    println!(
+                        "Logged in as {} ({}) on {}",
+                        login.profile.name, login.profile.id, &server.root
+                    );
+                    accounts.add(login.into());
+                }
+                Some(("add-offline", add_matches)) => {
+                    let player = add_matches.get_one::<String>("player").unwrap();
+                    let account = accounts.add_offline(player)?;
+                    println!("Added offline account {} ({})", account.name, account.uuid);
+                }
+                Some(("list", _)) => {
+                    let default = accounts.default_account().map(|a| a.uuid.clone());
+                    for account in accounts.accounts.iter() {
+                        let marker = if default.as_ref() == Some(&account.uuid) {
+                            "*"
+                        } else {
+                            " "
+                        };
+                        println!(
+                            "{}\t{}\t{}\t{}",
+                            marker, account.name, account.uuid, account.kind
+                        );
+                    }
+                }
+                Some(("use", use_matches)) => {
+                    let query = use_matches.get_one::<String>("account").unwrap();
+                    accounts.set_default(query)?;
+                }
+                Some(("remove", remove_matches)) => {
+                    let query = remove_matches.get_one::<String>("account").unwrap();
+                    let name = accounts.remove(query)?;
+                    println!("Removed {}", name);
+                }
+                _ => unreachable!("Subcommands are required!"),
+            }
+            accounts.save()?;
+        }
+        Some((command @ ("skin" | "cape"), matches)) => {
+            let account = auth::AccountStore::open(&config)?
+                .refreshed(
+                    &config,
+                    matches.get_one::<String>("account").map(String::as_str),
+                )
+                .await?;
+            if account.kind != auth::AccountKind::Microsoft {
+                anyhow::bail!(
+                    "{} is a {} account, only Microsoft accounts have skins and capes",
+                    account.name,
+                    account.kind
+                );
+            }
+            let services = auth::MinecraftServices::new(
+                &config.minecraft_services_url,
+                &account.tokens.access_token,
+            );
+            match (command, matches.subcommand()) {
+                ("skin", Some(("upload", upload_matches))) => {
+                    let png = std::fs::read(upload_matches.get_one::<String>("png").unwrap())?;
+                    let model = upload_matches.get_one::<String>("model").unwrap().parse()?;
+                    services.upload_skin(png, model).await?;
+                    println!("Uploaded the skin of {}", account.name);
+                }
+                ("skin", Some(("reset", _))) => {
+                    services.reset_skin().await?;
+                    println!("Reset the skin of {}", account.name);
+                }
+                ("cape", Some(("list", _))) => {
+                    for cape in services.profile().await?.capes {
+                        println!(
+                            "{}\t{}\t{}",
+                            if cape.is_active() { "*" } else { " " },
+                            cape.id,
+                            cape.alias.as_deref().unwrap_or_default()
+                        );
+                    }
+                }
+                ("cape", Some(("set", set_matches))) => {
+                    let cape = services
+                        .set_cape(set_matches.get_one::<String>("cape").unwrap())
+                        .await?;
+                    println!(
+                        "{} now wears {}",
+                        account.name,
+                        cape.alias.unwrap_or(cape.id)
+                    );
+                }
+                _ => unreachable!("Subcommands are required!"),
+            }
+        }
+        Some(("launch", launch_matches)) => {
+            let name = launch_matches.get_one::<String>("name").unwrap();
+            let inst = db.borrow().db.installation(name)?.clone();
+            let v = inst.load_version()?;
+            let settings = inst.settings.or(&config.launch);
+            let java = launch::java_for(&config, &settings, &v).await?;
+            let account = auth::AccountStore::open(&config)?
+                .refreshed(
+                    &config,
+                    launch_matches
+                        .get_one::<String>("account")
+                        .map(String::as_str),
+                )
+                .await?;
+            let mut launch_auth = launch::LaunchAuth::from(&account);
+            match account.kind {
+                auth::AccountKind::Offline => println!("Playing offline as {}", account.name),
+                auth::AccountKind::Yggdrasil => {
+                    let server =
+                        auth::Yggdrasil::new(account.server.as_deref().unwrap_or_default());
+                    launch_auth.jvm_args = server.jvm_args(&config).await?;
+                }
+                auth::AccountKind::Microsoft => {}
+            }
+            let command = launch::build(&inst, &v, &settings, &java, &launch_auth)?;
+            if launch_matches.contains_id("print") {
+                println!("{}", command);
             } else {
-                println!("version {} not found!", version_str)
+                launch::prepare(&inst, &v).await?;
+                std::fs::create_dir_all(&command.dir)?;
+                let started = std::time::SystemTime::now();
+                let logs_dir = inst.path.join(launch::LOGS_DIR);
+                let status = launch::supervise(&command, &logs_dir).await?;
+                println!("{} exited with {}", name, status);
+                if !status.success() {
+                    launch::explain_crash(
+                        &inst,
+                        &v,
+                        &settings,
+                        &logs_dir.join(launch::LATEST_LOG),
+                        started,
+                    )?;
+                }
+                exit_code = status.code().unwrap_or(1);
             }
         }
         _ => unreachable!("Subcommands are required!"),
     }
 
+    Ok(exit_code)
+}
+
+async fn manage_mods(
+    config: &Config,
+    inst: &Installation,
+    command: &str,
+    matches: &ArgMatches,
+) -> anyhow::Result<()> {
+    let modrinth = Modrinth::new(&config.modrinth_api_url);
+    let mut lock = Lockfile::load(inst)?;
+    match command {
+        "search" => {
+            let target = ModTarget::of(inst)?;
+            let query = matches.get_one::<String>("query").unwrap();
+            for hit in modrinth
+                .search(query, &target.game_version, &target.loader)
+                .await?
+            {
+                println!("{}\t{}\t{}", hit.slug, hit.title, hit.description);
+            }
+        }
+        "add" => {
+            let target = ModTarget::of(inst)?;
+            let request: ModRequest = matches.get_one::<String>("project").unwrap().parse()?;
+            let resolution = mods::resolve(&modrinth, &target, &lock, &[request]).await?;
+            for suggestion in resolution.suggestions.iter() {
+                println!("{}", suggestion);
+            }
+            let changes: Vec<_> = resolution.changes(&lock).cloned().collect();
+            for resolved in changes.iter() {
+                mods::install_version(inst, &mut lock, resolved).await?;
+                println!("Added {}", resolved.describe());
+            }
+            lock.set_target(&target);
+            lock.save(inst)?;
+        }
+        "update" => {
+            let target = ModTarget::of(inst)?;
+            let requests: Vec<ModRequest> = match matches.get_one::<String>("project") {
+                Some(project) => vec![lock
+                    .find(project)
+                    .map(|locked| locked.project_id.parse())
+                    .ok_or_else(|| anyhow::anyhow!("no mod {} in {}", project, inst.name))??],
+                None => lock
+                    .mods
+                    .iter()
+                    .map(|locked| locked.project_id.parse())
+                    .collect::<Result<_, _>>()?,
+            };
+            let resolution = mods::resolve(&modrinth, &target, &lock, &requests).await?;
+            let changes: Vec<_> = resolution.changes(&lock).cloned().collect();
+            if changes.is_empty() {
+                println!("Everything is up to date");
+            }
+            for resolved in changes.iter() {
+                match lock.find(&resolved.project.id) {
+                    Some(locked) => println!(
+                        "~ {} {} -> {}",
+                        resolved.project.title,
+                        locked.version_number,
+                        resolved.version.version_number
+                    ),
+                    None => println!("+ {}", resolved.describe()),
+                }
+            }
+            for resolved in changes.iter() {
+                mods::install_version(inst, &mut lock, resolved).await?;
+            }
+            lock.set_target(&target);
+            lock.save(inst)?;
+        }
+        "remove" => {
+            let project = matches.get_one::<String>("project").unwrap();
+            let locked = mods::remove(inst, &mut lock, project)?;
+            println!("Removed {}", locked.title);
+            lock.save(inst)?;
+        }
+        "list" => {
+            for locked in lock.mods.iter() {
+                println!(
+                    "{}\t{}\t{}",
+                    locked.slug, locked.version_number, locked.filename
+                );
+            }
+            for jar in mods::scan_dir(&mods::mods_dir(inst))? {
+                let file = jar.path.file_name().unwrap().to_string_lossy();
+                if lock.mods.iter().any(|locked| locked.filename == file) {
+                    continue;
+                }
+                if jar.mods.is_empty() {
+                    println!("?\t?\t{}\t(unmanaged)", file);
+                }
+                for m in jar.mods.iter() {
+                    println!(
+                        "{}\t{}\t{}\t(unmanaged: {})",
+                        m.id,
+                        m.version,
+                        file,
+                        m.display_name()
+                    );
+                }
+            }
+        }
+        "check" => {
+            let target = ModTarget::of(inst)?;
+            let loader = inst.loader.as_ref().unwrap();
+            let jars = mods::scan_dir(&mods::mods_dir(inst))?;
+            let problems = mods::check(&jars, &target.game_version, loader);
+            for problem in problems.iter() {
+                println!("{}", problem);
+            }
+            if !problems.is_empty() {
+                anyhow::bail!("found {} problems with the mods", problems.len());
+            }
+            println!("No problems found");
+        }
+        _ => unreachable!("Subcommands are required!"),
+    }
     Ok(())
 }
 
+async fn import_modpack(
+    config: &Config,
+    manifest: &VersionManifest,
+    file: &Path,
+) -> anyhow::Result<Installation> {
+    let mut pack = Modpack::open(file)?;
+    println!("Importing {}", pack.title());
+    let (game_version, loader) = pack.requirements()?;
+    let inst = install_with_loader(config, manifest, &game_version, loader.as_ref()).await?;
+    pack.install_to(&inst.game_dir(), config).await?;
+    Ok(inst)
+}
+
+async fn import_prism(
+    config: &Config,
+    manifest: &VersionManifest,
+    dir: &Path,
+    link: bool,
+) -> anyhow::Result<Installation> {
+    let instance = PrismInstance::open(dir)?;
+    println!("Importing instance {}", &instance.name);
+    let (game_version, loader) = instance.requirements()?;
+    let inst = install_with_loader(config, manifest, &game_version, loader.as_ref()).await?;
+    println!("Importing game files");
+    import::import_game_dir(&instance.minecraft_dir, &inst.game_dir(), link)?;
+    Ok(inst)
+}
+
+async fn import_vanilla(
+    config: &Config,
+    manifest: &VersionManifest,
+    root: &Path,
+) -> anyhow::Result<Vec<Installation>> {
+    let launcher = VanillaLauncher::open(root)?;
+    let mut insts = Vec::new();
+    for profile in launcher.profiles.iter() {
+        let name = profile.display_name();
+        let version = match profile.version_id(manifest) {
+            Some(version) => version,
+            None => {
+                println!("Skipping {} (no version)", name);
+                continue;
+            }
+        };
+        println!("Importing profile {} ({})", name, version);
+        let chain = match launcher.version_chain(&version, manifest).await {
+            Ok(chain) => chain,
+            Err(err) => {
+                println!("Skipping {} ({})", name, err);
+                continue;
+            }
+        };
+        let v = chain[0].clone().resolve(|id| {
+            chain
+                .iter()
+                .find(|raw| raw.id == id)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("version {} not found!", id))
+        })?;
+
+        let mut inst = Installation::new(&v, &config.db_path.join("installations"));
+        inst.ensure_dirs_exist()?;
+        for raw in chain.iter() {
+            std::fs::write(
+                inst.path.join(format!("{}.json", raw.id)),
+                serde_json::to_vec(raw)?.as_slice(),
+            )?;
+        }
+        if let Some((kind, loader_version)) = import::detect_loader(&v.id) {
+            inst.loader = Some(kind);
+            inst.loader_version = Some(loader_version);
+        }
+        inst.custom_game_dir = Some(profile.game_dir.clone().unwrap_or_else(|| root.into()));
+        inst.settings = profile.settings();
+
+        install_files(&inst, &v, Some(&launcher)).await?;
+        insts.push(inst);
+    }
+    Ok(insts)
+}
+
+async fn install_with_loader(
+    config: &Config,
+    manifest: &VersionManifest,
+    version: &str,
+    loader: Option<&LoaderSpec>,
+) -> anyhow::Result<Installation> {
+    let vinfo = manifest
+        .versions
+        .iter()
+        .find(|info| info.id == version)
+        .ok_or_else(|| anyhow::anyhow!("version {} not found!", version))?;
+    let mut inst = install(config, vinfo).await?;
+    if let Some(loader) = loader {
+        loader::install(config, &mut inst, loader).await?;
+    }
+    Ok(inst)
+}
+
 async fn install(config: &Config, version_info: &VersionInfo) -> anyhow::Result<Installation> {
     println!("Fetching version info...");
     let v = version_info.fetch_async().await?;
 
-    println!("Fetching asset index...");
-    let asset_index = v.asset_index.fetch_async().await?;
-
     let inst = Installation::new(&v, &config.db_path.join("installations"));
     inst.ensure_dirs_exist()?;
 
@@ -93,10 +937,39 @@ async fn install(config: &Config, version_info: &VersionInfo) -> anyhow::Result<
         serde_json::to_vec(&v)?.as_slice(),
     )?;
 
-    std::fs::write(
-        inst.path.join("asset_index.json"),
-        serde_json::to_vec(&asset_index)?.as_slice(),
-    )?;
+    install_files(&inst, &v, None).await?;
+    Ok(inst)
+}
+
+/// Downloads the libraries and assets of `v` into `inst`, reusing whatever
+/// the official launcher at `seed` already has.
+async fn install_files(
+    inst: &Installation,
+    v: &Version,
+    seed: Option<&VanillaLauncher>,
+) -> anyhow::Result<()> {
+    let asset_index = match &v.asset_index {
+        Some(artifact) => match seed.and_then(|launcher| launcher.asset_index(artifact)) {
+            Some(asset_index) => Some(asset_index),
+            None => {
+                println!("Fetching asset index...");
+                Some(artifact.fetch_async().await?)
+            }
+        },
+        None => None,
+    };
+
+    if let Some(asset_index) = &asset_index {
+        std::fs::write(
+            inst.path.join("asset_index.json"),
+            serde_json::to_vec(asset_index)?.as_slice(),
+        )?;
+    }
+
+    if let Some(launcher) = seed {
+        let reused = launcher.seed(inst, v, asset_index.as_ref())?;
+        println!("Reused {} files from {:?}", reused, &launcher.root);
+    }
 
     println!("Installing libraries");
     try_join_all(
@@ -106,13 +979,15 @@ async fn install(config: &Config, version_info: &VersionInfo) -> anyhow::Result<
     )
     .await?;
 
-    println!("Installing assets");
-    try_join_all(
-        asset_index
-            .resources()
-            .iter()
-            .map(|asset| asset.install_to(&inst.path)),
-    )
-    .await?;
-    Ok(inst)
+    if let Some(asset_index) = &asset_index {
+        println!("Installing assets");
+        try_join_all(
+            asset_index
+                .resources()
+                .iter()
+                .map(|asset| asset.install_to(&inst.path)),
+        )
+        .await?;
+    }
+    Ok(())
 }
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct InvalidCoordinateError(String);
+
+impl fmt::Display for InvalidCoordinateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid maven coordinate '{}'", self.0)
+    }
+}
+
+impl std::error::Error for InvalidCoordinateError {}
+
+/// A maven coordinate in the `group:artifact:version[:classifier][@extension]`
+/// form used by library names in version JSONs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coordinate {
+    pub group: String,
+    pub artifact: String,
+    pub version: String,
+    pub classifier: Option<String>,
+    pub extension: String,
+}
+
+impl Coordinate {
+    /// Identifies the library independently of its version, so that two
+    /// versions of the same artifact collide.
+    pub fn key(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!("{}:{}:{}", self.group, self.artifact, classifier),
+            None => format!("{}:{}", self.group, self.artifact),
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!(
+                "{}-{}-{}.{}",
+                self.artifact, self.version, classifier, self.extension
+            ),
+            None => format!("{}-{}.{}", self.artifact, self.version, self.extension),
+        }
+    }
+
+    /// Path of the artifact relative to the root of a maven repository.
+    pub fn path(&self) -> PathBuf {
+        let mut path = PathBuf::new();
+        for segment in self.group.split('.') {
+            path.push(segment);
+        }
+        path.join(&self.artifact)
+            .join(&self.version)
+            .join(self.file_name())
+    }
+
+    /// Url of the artifact inside the repository rooted at `base`.
+    pub fn url(&self, base: &str) -> String {
+        format!(
+            "{}/{}/{}/{}/{}",
+            base.trim_end_matches('/'),
+            self.group.replace('.', "/"),
+            self.artifact,
+            self.version,
+            self.file_name()
+        )
+    }
+}
+
+impl FromStr for Coordinate {
+    type Err = InvalidCoordinateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (coords, extension) = match s.split_once('@') {
+            Some((coords, ext)) => (coords, ext.to_string()),
+            None => (s, "jar".to_string()),
+        };
+        let parts: Vec<&str> = coords.split(':').collect();
+        if parts.len() < 3 || parts.len() > 4 || parts.iter().any(|p| p.is_empty()) {
+            return Err(InvalidCoordinateError(s.to_string()));
+        }
+        Ok(Self {
+            group: parts[0].to_string(),
+            artifact: parts[1].to_string(),
+            version: parts[2].to_string(),
+            classifier: parts.get(3).map(|c| c.to_string()),
+            extension,
+        })
+    }
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.artifact, self.version)?;
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{}", classifier)?;
+        }
+        if self.extension != "jar" {
+            write!(f, "@{}", self.extension)?;
+        }
+        Ok(())
+    }
+}
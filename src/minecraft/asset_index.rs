@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::minecraft::Artifact;
-use crate::minecraft::{Resource, ResourceType, Resources};
+use crate::minecraft::{Resource, Resources};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Asset {
@@ -19,12 +19,10 @@ impl Resources for AssetIndex {
     fn resources(&self) -> Vec<Resource> {
         self.objects
             .keys()
-            .into_iter()
             .map(|asset_name| {
                 let asset = &self.objects[asset_name];
                 let asset_artifact = Artifact::from(asset.clone());
                 Resource {
-                    resource_type: ResourceType::Asset,
                     name: asset_name.clone(),
                     artifacts: vec![asset_artifact],
                 }
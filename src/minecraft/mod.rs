@@ -1,9 +1,11 @@
 mod asset_index;
+mod raw_version;
 mod resource;
 mod version;
 mod version_manifest;
 
 pub use asset_index::*;
+pub use raw_version::*;
 pub use resource::*;
 pub use version::*;
 pub use version_manifest::*;
@@ -12,6 +14,5 @@ use async_trait::async_trait;
 
 #[async_trait]
 pub trait Fetch<T> {
-    fn fetch(&self) -> anyhow::Result<T>;
     async fn fetch_async(&self) -> anyhow::Result<T>;
 }
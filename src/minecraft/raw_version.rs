@@ -0,0 +1,287 @@
+use crate::minecraft::{
+    legacy_assets, Arguments, Artifact, AssetIndexArtifact, JavaVersion, Library, Logging,
+    ReleaseType, Version,
+};
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+/// A version JSON as found on disk, before its `inheritsFrom` chain has been
+/// resolved. Loader profiles (Fabric, Forge, OptiFine...) only carry the
+/// fields they override, so everything but the id is optional here.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RawVersion {
+    pub id: String,
+
+    #[serde(rename = "inheritsFrom")]
+    pub inherits_from: Option<String>,
+
+    #[serde(rename = "assetIndex")]
+    pub asset_index: Option<AssetIndexArtifact>,
+    pub assets: Option<String>,
+
+    #[serde(rename = "complianceLevel")]
+    pub compliance_level: Option<u32>,
+    pub downloads: Option<HashMap<String, Artifact>>,
+
+    #[serde(rename = "javaVersion")]
+    pub java_version: Option<JavaVersion>,
+
+    #[serde(default)]
+    pub libraries: Vec<Library>,
+
+    #[serde(rename = "mainClass")]
+    pub main_class: Option<String>,
+
+    pub arguments: Option<Arguments>,
+
+    #[serde(rename = "minecraftArguments")]
+    pub minecraft_arguments: Option<String>,
+
+    pub logging: Option<Logging>,
+
+    #[serde(rename = "releaseTime")]
+    pub release_time: Option<String>,
+    pub time: Option<String>,
+
+    #[serde(rename = "type")]
+    pub release_type: Option<ReleaseType>,
+}
+
+impl RawVersion {
+    /// Resolves the `inheritsFrom` chain of this version, loading each parent
+    /// through `load`, and merges it into a complete `Version`.
+    pub fn resolve<F>(self, mut load: F) -> anyhow::Result<Version>
+    where
+        F: FnMut(&str) -> anyhow::Result<RawVersion>,
+    {
+        let mut seen = HashSet::new();
+        seen.insert(self.id.clone());
+
+        let mut merged = self;
+        while let Some(parent_id) = merged.inherits_from.take() {
+            if !seen.insert(parent_id.clone()) {
+                bail!("version {} inherits from itself", parent_id);
+            }
+            let parent = load(&parent_id)?;
+            merged = parent.merge(merged);
+        }
+        Version::try_from(merged)
+    }
+
+    /// Lays `child` over `self`: scalar fields from the child win, libraries
+    /// are concatenated child-first with the parent's libraries shadowed by
+    /// any child library of the same coordinate and arguments are appended.
+    ///
+    /// Duplicates within one JSON are kept: vanilla lists some libraries
+    /// twice, with rules picking one per OS.
+    fn merge(self, child: RawVersion) -> RawVersion {
+        let shadowed: HashSet<String> = child.libraries.iter().map(library_key).collect();
+        let libraries = child
+            .libraries
+            .into_iter()
+            .chain(
+                self.libraries
+                    .into_iter()
+                    .filter(|lib| !shadowed.contains(&library_key(lib))),
+            )
+            .collect();
+
+        let arguments = match (self.arguments, child.arguments) {
+            (Some(mut parent), Some(child)) => {
+                parent.game.extend(child.game);
+                parent.jvm.extend(child.jvm);
+                Some(parent)
+            }
+            (parent, child) => child.or(parent),
+        };
+
+        RawVersion {
+            id: child.id,
+            inherits_from: self.inherits_from,
+            asset_index: child.asset_index.or(self.asset_index),
+            assets: child.assets.or(self.assets),
+            compliance_level: child.compliance_level.or(self.compliance_level),
+            downloads: child.downloads.or(self.downloads),
+            java_version: child.java_version.or(self.java_version),
+            libraries,
+            main_class: child.main_class.or(self.main_class),
+            arguments,
+            minecraft_arguments: child.minecraft_arguments.or(self.minecraft_arguments),
+            logging: child.logging.or(self.logging),
+            release_time: child.release_time.or(self.release_time),
+            time: child.time.or(self.time),
+            release_type: child.release_type.or(self.release_type),
+        }
+    }
+}
+
+fn library_key(lib: &Library) -> String {
+    lib.coordinate()
+        .map(|c| c.key())
+        .unwrap_or_else(|_| lib.name.clone())
+}
+
+impl TryFrom<RawVersion> for Version {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawVersion) -> anyhow::Result<Self> {
+        if let Some(parent) = raw.inherits_from {
+            bail!("version {} still inherits from {}", raw.id, parent);
+        }
+        let id = raw.id;
+        let missing = |field: &str| anyhow!("version {} is missing `{}`", id, field);
+        Ok(Version {
+            asset_index: raw.asset_index,
+            assets: raw.assets.unwrap_or_else(legacy_assets),
+            compliance_level: raw.compliance_level.unwrap_or_default(),
+            downloads: raw.downloads.ok_or_else(|| missing("downloads"))?,
+            java_version: raw.java_version.unwrap_or_default(),
+            libraries: raw.libraries,
+            main_class: raw.main_class.ok_or_else(|| missing("mainClass"))?,
+            arguments: raw.arguments,
+            minecraft_arguments: raw.minecraft_arguments,
+            logging: raw.logging,
+            release_time: raw.release_time.ok_or_else(|| missing("releaseTime"))?,
+            time: raw.time.ok_or_else(|| missing("time"))?,
+            release_type: raw.release_type.ok_or_else(|| missing("type"))?,
+            id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minecraft::Argument;
+    use serde_json::json;
+
+    fn raw(value: serde_json::Value) -> RawVersion {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn vanilla() -> RawVersion {
+        raw(json!({
+            "id": "1.16.5",
+            "assets": "1.16",
+            "downloads": {},
+            "mainClass": "net.minecraft.client.main.Main",
+            "arguments": { "game": ["--username"], "jvm": ["-cp"] },
+            "libraries": [
+                { "name": "com.mojang:brigadier:1.0.17" },
+                {
+                    "name": "org.lwjgl:lwjgl:3.2.2",
+                    "rules": [
+                        { "action": "allow" },
+                        { "action": "disallow", "os": { "name": "osx" } }
+                    ]
+                },
+                {
+                    "name": "org.lwjgl:lwjgl:3.2.1",
+                    "rules": [{ "action": "allow", "os": { "name": "osx" } }]
+                },
+                { "name": "org.ow2.asm:asm:7.2" }
+            ],
+            "releaseTime": "2021-01-14T16:05:32+00:00",
+            "time": "2021-01-14T16:05:32+00:00",
+            "type": "release"
+        }))
+    }
+
+    fn fabric() -> RawVersion {
+        raw(json!({
+            "id": "fabric-loader-0.14.9-1.16.5",
+            "inheritsFrom": "1.16.5",
+            "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+            "arguments": { "game": [], "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "] },
+            "libraries": [
+                { "name": "net.fabricmc:fabric-loader:0.14.9", "url": "https://maven.fabricmc.net/" },
+                { "name": "org.ow2.asm:asm:9.3", "url": "https://maven.fabricmc.net/" }
+            ]
+        }))
+    }
+
+    fn load(id: &str) -> anyhow::Result<RawVersion> {
+        match id {
+            "1.16.5" => Ok(vanilla()),
+            _ => bail!("no version {}", id),
+        }
+    }
+
+    fn names(v: &Version) -> Vec<&str> {
+        v.libraries.iter().map(|lib| lib.name.as_str()).collect()
+    }
+
+    #[test]
+    fn resolve_puts_child_libraries_first() {
+        let v = fabric().resolve(load).unwrap();
+        assert_eq!(
+            names(&v)[..2],
+            ["net.fabricmc:fabric-loader:0.14.9", "org.ow2.asm:asm:9.3"]
+        );
+        assert_eq!(v.id, "fabric-loader-0.14.9-1.16.5");
+        assert_eq!(
+            v.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+        assert_eq!(v.assets, "1.16");
+        assert_eq!(v.release_type, ReleaseType::Release);
+    }
+
+    #[test]
+    fn child_library_shadows_parent_coordinate() {
+        let v = fabric().resolve(load).unwrap();
+        assert!(names(&v).contains(&"org.ow2.asm:asm:9.3"));
+        assert!(!names(&v).contains(&"org.ow2.asm:asm:7.2"));
+    }
+
+    #[test]
+    fn duplicates_within_one_json_are_kept() {
+        let v = fabric().resolve(load).unwrap();
+        assert!(names(&v).contains(&"org.lwjgl:lwjgl:3.2.2"));
+        assert!(names(&v).contains(&"org.lwjgl:lwjgl:3.2.1"));
+
+        let v = vanilla().resolve(load).unwrap();
+        assert_eq!(v.libraries.len(), 4);
+    }
+
+    #[test]
+    fn arguments_are_appended() {
+        let v = fabric().resolve(load).unwrap();
+        let args = v.arguments.unwrap();
+        let plain = |args: &[Argument]| -> Vec<String> {
+            args.iter()
+                .filter_map(|arg| match arg {
+                    Argument::Plain(s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(plain(&args.game), ["--username"]);
+        assert_eq!(
+            plain(&args.jvm),
+            ["-cp", "-DFabricMcEmu= net.minecraft.client.main.Main "]
+        );
+    }
+
+    #[test]
+    fn inherits_from_cycle_is_an_error() {
+        let a = raw(json!({ "id": "a", "inheritsFrom": "b" }));
+        let err = a
+            .resolve(|id| {
+                Ok(raw(match id {
+                    "b" => json!({ "id": "b", "inheritsFrom": "a" }),
+                    _ => unreachable!(),
+                }))
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("inherits from itself"));
+    }
+
+    #[test]
+    fn missing_parent_is_an_error() {
+        let orphan = raw(json!({ "id": "orphan", "inheritsFrom": "nowhere" }));
+        assert!(orphan.resolve(load).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::checksum::{Checksum, ChecksumVerificationError};
-use crate::minecraft::{Asset, Library, OSName};
+use crate::minecraft::{Asset, Library};
 use crate::Fetch;
 use anyhow::ensure;
 use async_trait::async_trait;
@@ -7,22 +7,17 @@ use bytes::Bytes;
 
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
-use std::env;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 const RESOURCE_URL: &str = "https://resources.download.minecraft.net/";
 const RESOURCE_PATH: &str = ".minecraft/assets/objects/";
-
-#[derive(Debug)]
-pub enum ResourceType {
-    Asset,
-    Library,
-}
+const LIBRARIES_URL: &str = "https://libraries.minecraft.net/";
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Artifact {
-    pub sha1: String,
+    pub sha1: Option<String>,
+    #[serde(default)]
     pub size: u32,
     pub url: String,
     pub path: Option<PathBuf>,
@@ -43,7 +38,7 @@ impl From<Asset> for Artifact {
             .join(&asset.hash);
 
         Self {
-            sha1: asset.hash,
+            sha1: Some(asset.hash),
             size: asset.size,
             url: resource_url.to_string(),
             path: Some(path),
@@ -53,11 +48,6 @@ impl From<Asset> for Artifact {
 
 #[async_trait]
 impl Fetch<Bytes> for Artifact {
-    #[allow(unused)]
-    fn fetch(&self) -> anyhow::Result<Bytes> {
-        Ok(reqwest::blocking::get(&self.url)?.bytes()?)
-    }
-
     async fn fetch_async(&self) -> anyhow::Result<Bytes> {
         let resp = reqwest::get(&self.url).await?;
         let status = resp.status();
@@ -71,10 +61,14 @@ impl Fetch<Bytes> for Artifact {
 
 impl Checksum for Artifact {
     fn verify_checksum(&self, v: &Bytes) -> anyhow::Result<()> {
+        let sha1 = match &self.sha1 {
+            Some(sha1) => sha1,
+            None => return Ok(()),
+        };
         let mut hasher = Sha1::new();
         hasher.update(v);
         let res = hasher.finalize();
-        let sha1_hex = hex::decode(&self.sha1)?;
+        let sha1_hex = hex::decode(sha1)?;
         ensure!(res[..].eq(&sha1_hex), ChecksumVerificationError);
         Ok(())
     }
@@ -82,7 +76,6 @@ impl Checksum for Artifact {
 
 #[derive(Debug)]
 pub struct Resource {
-    pub resource_type: ResourceType,
     pub name: String,
     pub artifacts: Vec<Artifact>,
 }
@@ -91,20 +84,45 @@ impl From<Library> for Resource {
     fn from(lib: Library) -> Self {
         let mut artifacts = Vec::new();
 
-        if let Some(main_artifact) = lib.downloads.artifact {
-            artifacts.push(main_artifact)
+        if lib.downloads.is_none() {
+            let base = lib.url.as_deref().unwrap_or(LIBRARIES_URL);
+            // natives-only entries, like the legacy lwjgl-platform, have no
+            // main jar
+            if lib.natives.is_none() {
+                if let Ok(coordinate) = lib.coordinate() {
+                    artifacts.push(Artifact {
+                        sha1: lib.sha1.clone(),
+                        size: lib.size.unwrap_or_default(),
+                        url: coordinate.url(base),
+                        path: Some(coordinate.path()),
+                    })
+                }
+            } else if let Some(coordinate) = lib.native_coordinate() {
+                artifacts.push(Artifact {
+                    sha1: None,
+                    size: 0,
+                    url: coordinate.url(base),
+                    path: Some(coordinate.path()),
+                })
+            }
         }
 
-        if let Some(natives) = lib.natives {
-            let os_name = OSName::from_str(env::consts::OS).unwrap();
-            if let Some(classifier) = natives.get(&os_name) {
-                let dep_lib = &lib.downloads.classifiers.unwrap()[classifier];
-                artifacts.push(dep_lib.clone())
+        let native_classifier = lib.native_classifier();
+        if let Some(downloads) = lib.downloads {
+            if let Some(main_artifact) = downloads.artifact {
+                artifacts.push(main_artifact)
+            }
+
+            if let (Some(classifier), Some(classifiers)) =
+                (native_classifier, downloads.classifiers)
+            {
+                if let Some(dep_lib) = classifiers.get(&classifier) {
+                    artifacts.push(dep_lib.clone())
+                }
             }
         }
 
         Self {
-            resource_type: ResourceType::Library,
             name: lib.name,
             artifacts,
         }
@@ -122,8 +140,26 @@ impl Resource {
             };
             println!("  -> {:?}", &p);
             let dst_path = dst.join(p);
+            if artifact.url.is_empty() {
+                // generated locally, e.g. by the forge installer's processors
+                ensure!(
+                    dst_path.exists(),
+                    "{} has no download url and was not generated",
+                    &self.name
+                );
+                continue;
+            }
+            if dst_path.exists()
+                && artifact
+                    .verify_checksum(&std::fs::read(&dst_path)?.into())
+                    .is_ok()
+            {
+                continue;
+            }
             std::fs::create_dir_all(dst_path.parent().unwrap())?;
-            std::fs::write(dst_path, artifact.fetch_async().await?)?;
+            let buf = artifact.fetch_async().await?;
+            artifact.verify_checksum(&buf)?;
+            std::fs::write(dst_path, buf)?;
         }
         Ok(())
     }
@@ -133,7 +169,6 @@ impl From<Asset> for Resource {
     fn from(asset: Asset) -> Self {
         Self {
             name: asset.hash.clone(),
-            resource_type: ResourceType::Asset,
             artifacts: vec![Artifact::from(asset)],
         }
     }
@@ -142,3 +177,53 @@ impl From<Asset> for Resource {
 pub trait Resources {
     fn resources(&self) -> Vec<Resource>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(json: &str) -> Resource {
+        Resource::from(serde_json::from_str::<Library>(json).unwrap())
+    }
+
+    #[test]
+    fn library_without_downloads() {
+        let res = resource(
+            r#"{"name": "net.minecraft:launchwrapper:1.12", "url": "https://libraries.minecraft.net/"}"#,
+        );
+        assert_eq!(res.artifacts.len(), 1);
+        assert_eq!(
+            res.artifacts[0].url,
+            "https://libraries.minecraft.net/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar"
+        );
+    }
+
+    #[test]
+    fn natives_only_library_without_downloads() {
+        let res = resource(
+            r#"{
+                "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.1",
+                "natives": {"linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows-${arch}"}
+            }"#,
+        );
+        let classifier = match std::env::consts::OS {
+            "linux" => "natives-linux",
+            "macos" => "natives-osx",
+            "windows" if cfg!(target_pointer_width = "64") => "natives-windows-64",
+            "windows" => "natives-windows-32",
+            _ => {
+                assert!(res.artifacts.is_empty());
+                return;
+            }
+        };
+        // only the natives, there is no main jar
+        assert_eq!(res.artifacts.len(), 1);
+        assert_eq!(
+            res.artifacts[0].url,
+            format!(
+                "https://libraries.minecraft.net/org/lwjgl/lwjgl/lwjgl-platform/2.9.1/lwjgl-platform-2.9.1-{}.jar",
+                classifier
+            )
+        );
+    }
+}
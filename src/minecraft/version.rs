@@ -1,10 +1,12 @@
+use crate::maven;
 use crate::minecraft::{Artifact, ReleaseType, Resource};
-use crate::minecraft::{AssetIndex, Fetch, ResourceType, Resources};
+use crate::minecraft::{AssetIndex, Fetch, Resources};
 use async_trait::async_trait;
 use bytes::Buf;
 use reqwest::header::{ACCEPT, USER_AGENT};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::env;
 use std::fmt;
 use std::str::FromStr;
 
@@ -17,7 +19,7 @@ impl fmt::Display for UnknownOSError {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum RuleAction {
     #[serde(rename = "allow")]
     Allow,
@@ -47,15 +49,55 @@ impl FromStr for OSName {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RuleOS {
-    name: OSName,
+    name: Option<OSName>,
+    arch: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Rule {
     action: RuleAction,
-    os: RuleOS,
+    os: Option<RuleOS>,
+    features: Option<HashMap<String, bool>>,
+}
+
+impl Rule {
+    fn matches(&self, features: &HashMap<String, bool>) -> bool {
+        if let Some(os) = &self.os {
+            if let Some(name) = &os.name {
+                if OSName::from_str(env::consts::OS).ok().as_ref() != Some(name) {
+                    return false;
+                }
+            }
+            if let Some(arch) = &os.arch {
+                if arch != env::consts::ARCH {
+                    return false;
+                }
+            }
+        }
+        if let Some(required) = &self.features {
+            return required
+                .iter()
+                .all(|(feature, value)| features.get(feature).copied().unwrap_or(false) == *value);
+        }
+        true
+    }
+}
+
+/// Evaluates a rule list the way the official launcher does: an empty list
+/// allows everything, otherwise the last matching rule decides and nothing
+/// is allowed by default.
+pub fn rules_allow(rules: &[Rule], features: &HashMap<String, bool>) -> bool {
+    if rules.is_empty() {
+        return true;
+    }
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(features))
+        .map(|rule| rule.action == RuleAction::Allow)
+        .unwrap_or(false)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -72,12 +114,60 @@ pub struct LibraryExtract {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Library {
     pub name: String,
-    pub downloads: LibraryDownloads,
+    pub downloads: Option<LibraryDownloads>,
+
+    /// Root of the maven repository hosting the library, used by loader
+    /// profiles that ship without `downloads`.
+    pub url: Option<String>,
+    pub sha1: Option<String>,
+    pub size: Option<u32>,
     pub extract: Option<LibraryExtract>,
     pub natives: Option<HashMap<OSName, String>>,
+    pub rules: Option<Vec<Rule>>,
+
+    /// Side flags of legacy (pre-1.13) forge profiles.
+    pub clientreq: Option<bool>,
+    pub serverreq: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Library {
+    pub fn coordinate(&self) -> anyhow::Result<maven::Coordinate> {
+        Ok(maven::Coordinate::from_str(&self.name)?)
+    }
+
+    /// The classifier of this platform's natives, for libraries that have
+    /// them.
+    pub fn native_classifier(&self) -> Option<String> {
+        let os_name = OSName::from_str(env::consts::OS).ok()?;
+        // old versions template the classifier with the JVM bitness
+        let arch = if cfg!(target_pointer_width = "64") {
+            "64"
+        } else {
+            "32"
+        };
+        self.natives
+            .as_ref()?
+            .get(&os_name)
+            .map(|c| c.replace("${arch}", arch))
+    }
+
+    /// The maven coordinate of this platform's natives, for libraries
+    /// without `downloads` that have them.
+    pub fn native_coordinate(&self) -> Option<maven::Coordinate> {
+        let mut coordinate = self.coordinate().ok()?;
+        coordinate.classifier = Some(self.native_classifier()?);
+        Some(coordinate)
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        if self.clientreq == Some(false) {
+            return false;
+        }
+        rules_allow(self.rules.as_deref().unwrap_or_default(), &HashMap::new())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AssetIndexArtifact {
     pub id: String,
     pub sha1: String,
@@ -90,11 +180,6 @@ pub struct AssetIndexArtifact {
 
 #[async_trait]
 impl Fetch<AssetIndex> for AssetIndexArtifact {
-    fn fetch(&self) -> anyhow::Result<AssetIndex> {
-        let res = reqwest::blocking::get(&self.url)?;
-        Ok(serde_json::from_reader(res)?)
-    }
-
     async fn fetch_async(&self) -> anyhow::Result<AssetIndex> {
         let client = reqwest::Client::new();
         let res = client
@@ -109,7 +194,7 @@ impl Fetch<AssetIndex> for AssetIndexArtifact {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JavaVersion {
     pub component: String,
 
@@ -117,25 +202,96 @@ pub struct JavaVersion {
     pub major_version: u32,
 }
 
+/// Versions predating `javaVersion` all ran on Java 8.
+impl Default for JavaVersion {
+    fn default() -> Self {
+        Self {
+            component: "jre-legacy".to_string(),
+            major_version: 8,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ArgumentValue {
+    Single(String),
+    Many(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Argument {
+    Plain(String),
+    Conditional {
+        rules: Vec<Rule>,
+        value: ArgumentValue,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Arguments {
+    #[serde(default)]
+    pub game: Vec<Argument>,
+    #[serde(default)]
+    pub jvm: Vec<Argument>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoggingFile {
+    pub id: String,
+    pub sha1: String,
+    pub size: u32,
+    pub url: String,
+}
+
+/// A log4j configuration the game is started with, through `argument`
+/// (which references the file as `${path}`).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoggingConfig {
+    pub argument: String,
+    pub file: LoggingFile,
+    #[serde(rename = "type")]
+    pub config_type: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Logging {
+    pub client: Option<LoggingConfig>,
+}
+
+pub fn legacy_assets() -> String {
+    "legacy".to_string()
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Version {
     pub id: String,
 
     #[serde(rename = "assetIndex")]
-    pub asset_index: AssetIndexArtifact,
+    pub asset_index: Option<AssetIndexArtifact>,
+
+    #[serde(default = "legacy_assets")]
     pub assets: String,
 
-    #[serde(rename = "complianceLevel")]
+    #[serde(rename = "complianceLevel", default)]
     pub compliance_level: u32,
     pub downloads: HashMap<String, Artifact>,
 
-    #[serde(rename = "javaVersion")]
+    #[serde(rename = "javaVersion", default)]
     pub java_version: JavaVersion,
     pub libraries: Vec<Library>,
 
     #[serde(rename = "mainClass")]
     pub main_class: String,
 
+    pub arguments: Option<Arguments>,
+
+    #[serde(rename = "minecraftArguments")]
+    pub minecraft_arguments: Option<String>,
+
+    pub logging: Option<Logging>,
+
     #[serde(rename = "releaseTime")]
     pub release_time: String,
     pub time: String,
@@ -152,7 +308,6 @@ impl Resources for Version {
             .map(|id| {
                 let artifact = &self.downloads[id];
                 Resource {
-                    resource_type: ResourceType::Library,
                     name: format!("{}.jar", id),
                     artifacts: vec![artifact.clone()],
                 }
@@ -161,7 +316,160 @@ impl Resources for Version {
 
         self.libraries
             .iter()
+            .filter(|lib| lib.is_allowed())
             .for_each(|lib| resources.push(Resource::from(lib.clone())));
         resources
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minecraft::RawVersion;
+
+    /// Parses a fixture both as a complete version and through the
+    /// `inheritsFrom` resolution installed versions are loaded with.
+    fn parse(json: &str) -> Version {
+        let direct: Version = serde_json::from_str(json).unwrap();
+        let raw: RawVersion = serde_json::from_str(json).unwrap();
+        let resolved = raw.resolve(|id| panic!("no parent {}", id)).unwrap();
+        assert_eq!(direct.id, resolved.id);
+        assert_eq!(direct.assets, resolved.assets);
+        assert_eq!(direct.release_type, resolved.release_type);
+        assert_eq!(
+            direct.java_version.major_version,
+            resolved.java_version.major_version
+        );
+        resolved
+    }
+
+    fn assert_java_8(v: &Version) {
+        assert_eq!(v.java_version.component, "jre-legacy");
+        assert_eq!(v.java_version.major_version, 8);
+    }
+
+    fn library<'a>(v: &'a Version, name: &str) -> &'a Library {
+        v.libraries.iter().find(|lib| lib.name == name).unwrap()
+    }
+
+    /// The pre-1.6 versions share LWJGL 2.9.0 and JInput, natives included.
+    fn assert_lwjgl_2_9_0(v: &Version) {
+        let platform = library(v, "org.lwjgl.lwjgl:lwjgl-platform:2.9.0");
+        assert!(platform.downloads.as_ref().unwrap().artifact.is_none());
+        assert_eq!(
+            platform.native_classifier().is_some(),
+            OSName::from_str(env::consts::OS).is_ok()
+        );
+        assert_eq!(platform.extract.as_ref().unwrap().exclude, ["META-INF/"]);
+        // LWJGL 2.9.0 is disallowed on macOS
+        assert_eq!(
+            library(v, "org.lwjgl.lwjgl:lwjgl:2.9.0").is_allowed(),
+            env::consts::OS != "macos"
+        );
+        assert!(library(v, "net.java.jinput:jinput-platform:2.0.5").is_allowed());
+    }
+
+    #[test]
+    fn old_alpha() {
+        let v = parse(include_str!("fixtures/rd-132211.json"));
+        assert_eq!(v.release_type, ReleaseType::OldAlpha);
+        assert_java_8(&v);
+        assert_eq!(v.asset_index.as_ref().unwrap().id, "pre-1.6");
+        assert_eq!(v.assets, "pre-1.6");
+        assert_eq!(v.compliance_level, 0);
+        assert_eq!(v.main_class, "com.mojang.rubydung.RubyDung");
+        assert!(v.arguments.is_none());
+        assert!(v.logging.is_none());
+        assert!(v.minecraft_arguments.is_some());
+        assert_lwjgl_2_9_0(&v);
+    }
+
+    #[test]
+    fn old_beta() {
+        let v = parse(include_str!("fixtures/b1.7.3.json"));
+        assert_eq!(v.release_type, ReleaseType::OldBeta);
+        assert_java_8(&v);
+        assert_eq!(v.assets, "pre-1.6");
+        assert_eq!(v.main_class, "net.minecraft.launchwrapper.Launch");
+        assert!(v
+            .minecraft_arguments
+            .as_deref()
+            .unwrap()
+            .ends_with("--tweakClass net.minecraft.launchwrapper.AlphaVanillaTweaker"));
+        assert_lwjgl_2_9_0(&v);
+    }
+
+    #[test]
+    fn minecraft_arguments() {
+        let v = parse(include_str!("fixtures/1.12.2.json"));
+        assert_eq!(v.release_type, ReleaseType::Release);
+        assert_java_8(&v);
+        assert_eq!(v.asset_index.as_ref().unwrap().id, "1.12");
+        assert_eq!(v.assets, "1.12");
+        assert!(v.arguments.is_none());
+        assert!(v
+            .minecraft_arguments
+            .as_deref()
+            .unwrap()
+            .contains("${auth_player_name}"));
+        assert_eq!(
+            v.downloads["client"].sha1.as_deref(),
+            Some("0f275bc1547d01fa5f56ba34bdc87d981ee12daf")
+        );
+        // one LWJGL build per OS
+        let lwjgl: Vec<_> = v
+            .libraries
+            .iter()
+            .filter(|lib| lib.name.starts_with("org.lwjgl.lwjgl:lwjgl:") && lib.is_allowed())
+            .collect();
+        assert_eq!(lwjgl.len(), 1);
+        let logging = v.logging.unwrap().client.unwrap();
+        assert_eq!(logging.file.id, "client-1.12.xml");
+        assert_eq!(logging.argument, "-Dlog4j.configurationFile=${path}");
+    }
+
+    #[test]
+    fn arguments() {
+        let v = parse(include_str!("fixtures/1.19.2.json"));
+        assert_eq!(v.java_version.component, "java-runtime-gamma");
+        assert_eq!(v.java_version.major_version, 17);
+        assert_eq!(v.compliance_level, 1);
+        assert_eq!(v.assets, "1.19");
+        assert!(v.minecraft_arguments.is_none());
+        let args = v.arguments.as_ref().unwrap();
+        let conditional: Vec<_> = args
+            .game
+            .iter()
+            .filter_map(|arg| match arg {
+                Argument::Conditional { value, .. } => Some(value),
+                Argument::Plain(_) => None,
+            })
+            .collect();
+        assert!(
+            matches!(conditional[..], [ArgumentValue::Single(demo), ArgumentValue::Many(resolution)]
+                if demo == "--demo" && resolution.len() == 4)
+        );
+        assert!(matches!(args.jvm.last(), Some(Argument::Plain(arg)) if arg == "${classpath}"));
+        // natives are libraries of their own, picked by OS rules
+        let natives = library(&v, "org.lwjgl:lwjgl:3.3.1:natives-linux");
+        assert!(natives.natives.is_none());
+        assert_eq!(natives.is_allowed(), env::consts::OS == "linux");
+        assert_eq!(
+            library(&v, "ca.weblite:java-objc-bridge:1.1").is_allowed(),
+            env::consts::OS == "macos"
+        );
+    }
+
+    #[test]
+    fn pending() {
+        let v = parse(include_str!("fixtures/pending.json"));
+        assert_eq!(v.release_type, ReleaseType::Unknown("pending".to_string()));
+        assert_java_8(&v);
+        assert!(v.asset_index.is_none());
+        assert_eq!(v.assets, legacy_assets());
+        assert!(v.libraries.is_empty());
+        // unknown types are kept verbatim
+        let json = serde_json::to_value(&v).unwrap();
+        assert_eq!(json["type"], "pending");
+    }
+}
@@ -6,16 +6,40 @@ use serde::{Deserialize, Serialize};
 const VERISON_MANIFEST_URL: &str =
     "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
 pub enum ReleaseType {
-    #[serde(rename = "release")]
     Release,
-    #[serde(rename = "snapshot")]
     Snapshot,
-    #[serde(rename = "old_beta")]
     OldBeta,
-    #[serde(rename = "old_alpha")]
     OldAlpha,
+    /// Anything else the manifest has carried over the years (`pending`,
+    /// april fools versions...), kept verbatim.
+    Unknown(String),
+}
+
+impl From<String> for ReleaseType {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "release" => ReleaseType::Release,
+            "snapshot" => ReleaseType::Snapshot,
+            "old_beta" => ReleaseType::OldBeta,
+            "old_alpha" => ReleaseType::OldAlpha,
+            _ => ReleaseType::Unknown(s),
+        }
+    }
+}
+
+impl From<ReleaseType> for String {
+    fn from(release_type: ReleaseType) -> Self {
+        match release_type {
+            ReleaseType::Release => "release".to_string(),
+            ReleaseType::Snapshot => "snapshot".to_string(),
+            ReleaseType::OldBeta => "old_beta".to_string(),
+            ReleaseType::OldAlpha => "old_alpha".to_string(),
+            ReleaseType::Unknown(s) => s,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -28,19 +52,12 @@ pub struct VersionInfo {
     #[serde(rename = "releaseTime")]
     pub release_time: String,
     pub sha1: String,
-    #[serde(rename = "complianceLevel")]
+    #[serde(rename = "complianceLevel", default)]
     pub compliance_level: i32,
 }
 
 #[async_trait]
 impl Fetch<Version> for VersionInfo {
-    #[allow(unused)]
-    fn fetch(&self) -> anyhow::Result<Version> {
-        let res = reqwest::blocking::get(&self.url)?;
-        let v = serde_json::from_reader(res)?;
-        Ok(v)
-    }
-
     #[allow(unused)]
     async fn fetch_async(&self) -> anyhow::Result<Version> {
         let res = reqwest::get(&self.url).await?;
@@ -51,8 +68,8 @@ impl Fetch<Version> for VersionInfo {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct VersionLatestInfo {
-    release: String,
-    snapshot: String,
+    pub release: String,
+    pub snapshot: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -74,3 +91,43 @@ impl VersionManifest {
         Ok(serde_json::from_reader(res.reader())?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_accepts_unknown_release_types() {
+        let manifest: VersionManifest = serde_json::from_str(
+            r#"{
+                "latest": { "release": "1.19.2", "snapshot": "1.19.2" },
+                "versions": [
+                    {
+                        "id": "1.RV-Pre1",
+                        "type": "pending",
+                        "url": "https://piston-meta.mojang.com/v1/packages/0/1.RV-Pre1.json",
+                        "time": "2016-03-31T16:39:41+00:00",
+                        "releaseTime": "2016-03-31T16:39:41+00:00",
+                        "sha1": "0"
+                    },
+                    {
+                        "id": "a1.0.4",
+                        "type": "old_alpha",
+                        "url": "https://piston-meta.mojang.com/v1/packages/0/a1.0.4.json",
+                        "time": "2013-08-31T00:00:00+00:00",
+                        "releaseTime": "2010-07-09T22:00:00+00:00",
+                        "sha1": "0",
+                        "complianceLevel": 0
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.versions[0].release_type,
+            ReleaseType::Unknown("pending".to_string())
+        );
+        assert_eq!(manifest.versions[0].compliance_level, 0);
+        assert_eq!(manifest.versions[1].release_type, ReleaseType::OldAlpha);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::checksum::{sha1_hex, ChecksumVerificationError};
+use crate::loader::{LoaderKind, LoaderSpec};
+use crate::modpack::{extract_overrides, safe_join};
+use anyhow::{anyhow, ensure};
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use zip::ZipArchive;
+
+pub const CURSEFORGE_MANIFEST: &str = "manifest.json";
+
+/// CurseForge class ids of the project types a pack can reference.
+const CLASS_RESOURCE_PACKS: u32 = 12;
+const CLASS_SHADER_PACKS: u32 = 6552;
+
+/// CurseForge's `hashes[].algo` value for sha1.
+const HASH_ALGO_SHA1: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModLoader {
+    pub id: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestMinecraft {
+    pub version: String,
+    #[serde(rename = "modLoaders", default)]
+    pub mod_loaders: Vec<ModLoader>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestFile {
+    #[serde(rename = "projectID")]
+    pub project_id: u32,
+    #[serde(rename = "fileID")]
+    pub file_id: u32,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// `manifest.json`, the index of a CurseForge modpack zip.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurseManifest {
+    pub minecraft: ManifestMinecraft,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    pub files: Vec<ManifestFile>,
+    #[serde(default = "default_overrides")]
+    pub overrides: String,
+}
+
+fn default_overrides() -> String {
+    "overrides".to_string()
+}
+
+impl CurseManifest {
+    /// The minecraft version and loader of the pack; loader ids look like
+    /// `forge-43.1.1` or `fabric-0.14.9`.
+    pub fn requirements(&self) -> anyhow::Result<(String, Option<LoaderSpec>)> {
+        let loader = self
+            .minecraft
+            .mod_loaders
+            .iter()
+            .find(|loader| loader.primary)
+            .or_else(|| self.minecraft.mod_loaders.first());
+        let loader = match loader {
+            Some(loader) => {
+                let (kind, version) = loader
+                    .id
+                    .split_once('-')
+                    .ok_or_else(|| anyhow!("unrecognised mod loader '{}'", loader.id))?;
+                Some(LoaderSpec {
+                    kind: LoaderKind::from_str(kind)?,
+                    version: Some(version.to_string()),
+                })
+            }
+            None => None,
+        };
+        Ok((self.minecraft.version.clone(), loader))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileHash {
+    pub value: String,
+    pub algo: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurseFile {
+    pub id: u32,
+    #[serde(rename = "modId")]
+    pub mod_id: u32,
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    /// Absent when the author disallows third-party distribution.
+    #[serde(rename = "downloadUrl")]
+    pub download_url: Option<String>,
+    #[serde(default)]
+    pub hashes: Vec<FileHash>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurseModLinks {
+    #[serde(rename = "websiteUrl")]
+    pub website_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CurseMod {
+    pub id: u32,
+    pub name: String,
+    #[serde(rename = "classId")]
+    pub class_id: Option<u32>,
+    pub links: CurseModLinks,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DataResponse<T> {
+    data: Vec<T>,
+}
+
+#[derive(Serialize, Debug)]
+struct FileIdsQuery<'a> {
+    #[serde(rename = "fileIds")]
+    file_ids: &'a [u32],
+}
+
+#[derive(Serialize, Debug)]
+struct ModIdsQuery<'a> {
+    #[serde(rename = "modIds")]
+    mod_ids: &'a [u32],
+}
+
+pub struct CurseForge {
+    base_url: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+impl CurseForge {
+    pub fn new(base_url: &str, api_key: Option<&str>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.map(str::to_string),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn post<Q: Serialize, T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        query: &Q,
+    ) -> anyhow::Result<Vec<T>> {
+        let mut req = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .json(query);
+        if let Some(key) = &self.api_key {
+            req = req.header("x-api-key", key);
+        }
+        let res: DataResponse<T> = req.send().await?.error_for_status()?.json().await?;
+        Ok(res.data)
+    }
+
+    pub async fn files(&self, file_ids: &[u32]) -> anyhow::Result<Vec<CurseFile>> {
+        self.post("/v1/mods/files", &FileIdsQuery { file_ids })
+            .await
+    }
+
+    pub async fn mods(&self, mod_ids: &[u32]) -> anyhow::Result<Vec<CurseMod>> {
+        self.post("/v1/mods", &ModIdsQuery { mod_ids }).await
+    }
+}
+
+/// A file the pack needs but CurseForge will not hand out, with the page it
+/// has to be downloaded from by hand.
+#[derive(Debug, Clone)]
+pub struct BlockedFile {
+    pub name: String,
+    pub file_name: String,
+    pub url: String,
+    pub dst: String,
+}
+
+/// A file of the pack the CurseForge API did not return at all, e.g.
+/// because it or its project was taken down.
+#[derive(Debug, Clone)]
+pub struct MissingFile {
+    pub project_id: u32,
+    pub file_id: u32,
+    /// The project's name, when the project itself is still there.
+    pub name: Option<String>,
+    pub url: String,
+}
+
+/// The files of a pack that were not installed.
+#[derive(Debug, Clone, Default)]
+pub struct SkippedFiles {
+    pub blocked: Vec<BlockedFile>,
+    pub missing: Vec<MissingFile>,
+}
+
+pub struct CursePack {
+    archive: ZipArchive<File>,
+    pub manifest: CurseManifest,
+}
+
+impl CursePack {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let mut buf = Vec::new();
+        archive
+            .by_name(CURSEFORGE_MANIFEST)?
+            .read_to_end(&mut buf)?;
+        let manifest = serde_json::from_slice(&buf)?;
+        Ok(Self { archive, manifest })
+    }
+
+    /// Downloads the pack's files and applies its overrides into `game_dir`,
+    /// returning the files that have to be fetched manually or could not be
+    /// found.
+    pub async fn install_to(
+        &mut self,
+        game_dir: &Path,
+        api: &CurseForge,
+    ) -> anyhow::Result<SkippedFiles> {
+        let file_ids: Vec<u32> = self
+            .manifest
+            .files
+            .iter()
+            .filter(|file| file.required)
+            .map(|file| file.file_id)
+            .collect();
+        let mod_ids: Vec<u32> = self
+            .manifest
+            .files
+            .iter()
+            .filter(|file| file.required)
+            .map(|file| file.project_id)
+            .collect();
+
+        println!("Resolving {} files", file_ids.len());
+        let files = api.files(&file_ids).await?;
+        let mods: HashMap<u32, CurseMod> = api
+            .mods(&mod_ids)
+            .await?
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect();
+
+        let mut downloads = Vec::new();
+        let mut skipped = SkippedFiles::default();
+        let returned: HashSet<u32> = files.iter().map(|file| file.id).collect();
+        for file in self.manifest.files.iter() {
+            if file.required && !returned.contains(&file.file_id) {
+                skipped
+                    .missing
+                    .push(missing_file(file.project_id, file.file_id, &mods));
+            }
+        }
+        for file in files.iter() {
+            let project = match mods.get(&file.mod_id) {
+                Some(project) => project,
+                None => {
+                    skipped
+                        .missing
+                        .push(missing_file(file.mod_id, file.id, &mods));
+                    continue;
+                }
+            };
+            let dir = match project.class_id {
+                Some(CLASS_RESOURCE_PACKS) => "resourcepacks",
+                Some(CLASS_SHADER_PACKS) => "shaderpacks",
+                _ => "mods",
+            };
+            let dst = format!("{}/{}", dir, file.file_name);
+            match &file.download_url {
+                Some(url) => downloads.push(install_file(file, url, safe_join(game_dir, &dst)?)),
+                None => skipped.blocked.push(BlockedFile {
+                    name: project.name.clone(),
+                    file_name: file.file_name.clone(),
+                    url: format!("{}/files/{}", project.links.website_url, file.id),
+                    dst,
+                }),
+            }
+        }
+        try_join_all(downloads).await?;
+
+        let overrides = self.manifest.overrides.clone();
+        let count = extract_overrides(&mut self.archive, &overrides, game_dir)?;
+        if count > 0 {
+            println!("Applied {} files from {}/", count, overrides);
+        }
+        Ok(skipped)
+    }
+}
+
+fn missing_file(project_id: u32, file_id: u32, mods: &HashMap<u32, CurseMod>) -> MissingFile {
+    let project = mods.get(&project_id);
+    MissingFile {
+        project_id,
+        file_id,
+        name: project.map(|project| project.name.clone()),
+        url: match project {
+            Some(project) => format!("{}/files/{}", project.links.website_url, file_id),
+            None => format!("https://www.curseforge.com/projects/{}", project_id),
+        },
+    }
+}
+
+async fn install_file(file: &CurseFile, url: &str, dst: std::path::PathBuf) -> anyhow::Result<()> {
+    println!("=> {}", &file.file_name);
+    let buf = reqwest::get(url).await?.error_for_status()?.bytes().await?;
+    if let Some(sha1) = file.hashes.iter().find(|h| h.algo == HASH_ALGO_SHA1) {
+        ensure!(
+            sha1_hex(&buf) == sha1.value,
+            "{} ({})",
+            &file.file_name,
+            ChecksumVerificationError
+        );
+    }
+    std::fs::create_dir_all(dst.parent().unwrap())?;
+    std::fs::write(dst, buf)?;
+    Ok(())
+}
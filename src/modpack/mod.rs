@@ -0,0 +1,163 @@
+mod curseforge;
+mod mrpack;
+
+pub use curseforge::*;
+pub use mrpack::*;
+
+use crate::config::Config;
+use crate::loader::LoaderSpec;
+use anyhow::{anyhow, bail, ensure};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use zip::ZipArchive;
+
+pub enum Modpack {
+    Modrinth(Mrpack),
+    CurseForge(CursePack),
+}
+
+impl Modpack {
+    /// Opens a modpack archive, telling the formats apart by their index.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        if archive.by_name(MRPACK_INDEX).is_ok() {
+            Ok(Modpack::Modrinth(Mrpack::open(path)?))
+        } else if archive.by_name(CURSEFORGE_MANIFEST).is_ok() {
+            Ok(Modpack::CurseForge(CursePack::open(path)?))
+        } else {
+            bail!("{:?} is neither a Modrinth nor a CurseForge modpack", path)
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Modpack::Modrinth(pack) => format!("{} {}", pack.index.name, pack.index.version_id),
+            Modpack::CurseForge(pack) => {
+                format!("{} {}", pack.manifest.name, pack.manifest.version)
+            }
+        }
+    }
+
+    pub fn requirements(&self) -> anyhow::Result<(String, Option<LoaderSpec>)> {
+        match self {
+            Modpack::Modrinth(pack) => pack.index.requirements(),
+            Modpack::CurseForge(pack) => pack.manifest.requirements(),
+        }
+    }
+
+    pub async fn install_to(&mut self, game_dir: &Path, config: &Config) -> anyhow::Result<()> {
+        match self {
+            Modpack::Modrinth(pack) => pack.install_to(game_dir).await,
+            Modpack::CurseForge(pack) => {
+                let api = CurseForge::new(
+                    &config.curseforge_api_url,
+                    config.curseforge_api_key.as_deref(),
+                );
+                let skipped = pack.install_to(game_dir, &api).await?;
+                if !skipped.missing.is_empty() {
+                    println!(
+                        "{} files could not be found on CurseForge, the pack is incomplete without them:",
+                        skipped.missing.len()
+                    );
+                    for file in skipped.missing.iter() {
+                        match &file.name {
+                            Some(name) => {
+                                println!("  {} (file {}): {}", name, file.file_id, file.url)
+                            }
+                            None => println!(
+                                "  project {} (file {}): {}",
+                                file.project_id, file.file_id, file.url
+                            ),
+                        }
+                    }
+                }
+                if !skipped.blocked.is_empty() {
+                    println!(
+                        "{} files do not allow third-party downloads, fetch them manually:",
+                        skipped.blocked.len()
+                    );
+                    for file in skipped.blocked.iter() {
+                        println!(
+                            "  {} ({}): {}\n    -> {:?}",
+                            file.name,
+                            file.file_name,
+                            file.url,
+                            game_dir.join(&file.dst)
+                        );
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Joins a path taken from a pack onto `root`, refusing anything that would
+/// escape it.
+pub fn safe_join(root: &Path, rel: &str) -> anyhow::Result<PathBuf> {
+    let rel = Path::new(rel);
+    ensure!(
+        rel.components().all(|c| matches!(c, Component::Normal(_))),
+        "refusing to write outside of the instance: {:?}",
+        rel
+    );
+    Ok(root.join(rel))
+}
+
+/// Copies every file under `prefix/` in the archive into `dst`, returning
+/// how many were written.
+pub fn extract_overrides(
+    archive: &mut ZipArchive<File>,
+    prefix: &str,
+    dst: &Path,
+) -> anyhow::Result<usize> {
+    let prefix = format!("{}/", prefix.trim_end_matches('/'));
+    let mut count = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let rel = match entry.name().strip_prefix(&prefix) {
+            Some(rel) => rel.to_string(),
+            None => continue,
+        };
+        let path = safe_join(dst, &rel)?;
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let mut buf = Vec::new();
+        entry.read_to_end(&mut buf)?;
+        std::fs::write(path, buf)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Lists the files below `dir`, recursively.
+pub fn walk(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(walk(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Downloads from the first of `urls` that answers.
+pub async fn fetch_first(urls: &[String]) -> anyhow::Result<bytes::Bytes> {
+    let mut last_err = anyhow!("no download url");
+    for url in urls.iter() {
+        match reqwest::get(url)
+            .await
+            .and_then(|res| res.error_for_status())
+        {
+            Ok(res) => return Ok(res.bytes().await?),
+            Err(err) => last_err = err.into(),
+        }
+    }
+    Err(last_err)
+}
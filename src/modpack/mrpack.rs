@@ -0,0 +1,261 @@
+use crate::checksum::{sha1_hex, verify_hashes};
+use crate::db::Installation;
+use crate::loader::{LoaderKind, LoaderSpec};
+use crate::modpack::{extract_overrides, fetch_first, safe_join, walk};
+use crate::modrinth::Modrinth;
+use anyhow::{anyhow, ensure};
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+pub const MRPACK_INDEX: &str = "modrinth.index.json";
+
+/// `dependencies` keys naming each loader.
+const LOADER_DEPENDENCIES: [(&str, LoaderKind); 4] = [
+    ("fabric-loader", LoaderKind::Fabric),
+    ("quilt-loader", LoaderKind::Quilt),
+    ("forge", LoaderKind::Forge),
+    ("neoforge", LoaderKind::NeoForge),
+];
+
+/// Top-level game directory entries that make up a pack. Everything else is
+/// left out, in particular what the official launcher keeps in a shared
+/// `.minecraft` (`versions`, `libraries`, `launcher_profiles.json`...).
+const EXPORT_INCLUDED: [&str; 11] = [
+    "config",
+    "defaultconfigs",
+    "kubejs",
+    "mods",
+    "resourcepacks",
+    "scripts",
+    "shaderpacks",
+    "options.txt",
+    "optionsof.txt",
+    "optionsshaders.txt",
+    "servers.dat",
+];
+
+/// Directories whose files may be found on Modrinth.
+const EXPORT_IDENTIFIABLE: [&str; 3] = ["mods", "resourcepacks", "shaderpacks"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum EnvSupport {
+    #[serde(rename = "required")]
+    Required,
+    #[serde(rename = "optional")]
+    Optional,
+    #[serde(rename = "unsupported")]
+    Unsupported,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileEnv {
+    pub client: EnvSupport,
+    pub server: EnvSupport,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackFile {
+    pub path: String,
+    pub hashes: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<FileEnv>,
+    pub downloads: Vec<String>,
+
+    #[serde(rename = "fileSize")]
+    pub file_size: u64,
+}
+
+impl PackFile {
+    pub fn on_client(&self) -> bool {
+        match &self.env {
+            Some(env) => env.client != EnvSupport::Unsupported,
+            None => true,
+        }
+    }
+
+    fn verify(&self, buf: &[u8]) -> anyhow::Result<()> {
+        verify_hashes(&self.hashes, buf)
+    }
+}
+
+/// `modrinth.index.json`, the manifest at the root of a `.mrpack`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackIndex {
+    #[serde(rename = "formatVersion")]
+    pub format_version: u32,
+    pub game: String,
+
+    #[serde(rename = "versionId")]
+    pub version_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<PackFile>,
+    pub dependencies: HashMap<String, String>,
+}
+
+impl PackIndex {
+    /// The minecraft version and loader called for by `dependencies`.
+    pub fn requirements(&self) -> anyhow::Result<(String, Option<LoaderSpec>)> {
+        let game_version = self
+            .dependencies
+            .get("minecraft")
+            .ok_or_else(|| anyhow!("pack does not depend on minecraft"))?;
+        let loader = LOADER_DEPENDENCIES.iter().find_map(|(dep, kind)| {
+            self.dependencies.get(*dep).map(|version| LoaderSpec {
+                kind: kind.clone(),
+                version: Some(version.clone()),
+            })
+        });
+        Ok((game_version.clone(), loader))
+    }
+}
+
+pub struct Mrpack {
+    archive: ZipArchive<File>,
+    pub index: PackIndex,
+}
+
+impl Mrpack {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let mut buf = Vec::new();
+        archive.by_name(MRPACK_INDEX)?.read_to_end(&mut buf)?;
+        let index: PackIndex = serde_json::from_slice(&buf)?;
+        ensure!(
+            index.game == "minecraft",
+            "pack is for {}, not minecraft",
+            index.game
+        );
+        Ok(Self { archive, index })
+    }
+
+    /// Downloads the pack's client files and applies its overrides into
+    /// `game_dir`.
+    pub async fn install_to(&mut self, game_dir: &Path) -> anyhow::Result<()> {
+        let files: Vec<&PackFile> = self
+            .index
+            .files
+            .iter()
+            .filter(|file| file.on_client())
+            .collect();
+        println!("Downloading {} files", files.len());
+        try_join_all(files.into_iter().map(|file| install_file(file, game_dir))).await?;
+
+        for prefix in ["overrides", "client-overrides"] {
+            let count = extract_overrides(&mut self.archive, prefix, game_dir)?;
+            if count > 0 {
+                println!("Applied {} files from {}/", count, prefix);
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn install_file(file: &PackFile, game_dir: &Path) -> anyhow::Result<()> {
+    let dst = safe_join(game_dir, &file.path)?;
+    println!("=> {}", &file.path);
+    let buf = fetch_first(&file.downloads).await?;
+    file.verify(&buf)
+        .map_err(|err| anyhow!("{} ({})", &file.path, err))?;
+    std::fs::create_dir_all(dst.parent().unwrap())?;
+    std::fs::write(dst, buf)?;
+    Ok(())
+}
+
+/// Packs the game directory of `inst` into a `.mrpack` at `out`. Files that
+/// Modrinth recognises by hash are referenced by url, everything else is
+/// stored under `overrides/`.
+pub async fn export(
+    inst: &Installation,
+    modrinth: &Modrinth,
+    version_id: &str,
+    out: &Path,
+) -> anyhow::Result<()> {
+    let game_dir = inst.game_dir();
+    let mut dependencies = HashMap::new();
+    dependencies.insert("minecraft".to_string(), inst.game_version()?);
+    if let (Some(kind), Some(version)) = (&inst.loader, &inst.loader_version) {
+        let (key, _) = LOADER_DEPENDENCIES.iter().find(|(_, k)| k == kind).unwrap();
+        dependencies.insert(key.to_string(), version.clone());
+    }
+
+    let mut candidates = Vec::new();
+    let mut overrides = Vec::new();
+    for top in EXPORT_INCLUDED {
+        let entry = game_dir.join(top);
+        let paths = if entry.is_dir() {
+            walk(&entry)?
+        } else if entry.is_file() {
+            vec![entry]
+        } else {
+            continue;
+        };
+        for path in paths {
+            let rel = path
+                .strip_prefix(&game_dir)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            let buf = std::fs::read(&path)?;
+            if rel.contains('/') && EXPORT_IDENTIFIABLE.contains(&top) {
+                candidates.push((rel, buf));
+            } else {
+                overrides.push((rel, buf));
+            }
+        }
+    }
+
+    println!("Identifying {} files on Modrinth", candidates.len());
+    let hashes: Vec<String> = candidates.iter().map(|(_, buf)| sha1_hex(buf)).collect();
+    let versions = modrinth.versions_from_hashes(&hashes).await?;
+
+    let mut files = Vec::new();
+    for ((rel, buf), sha1) in candidates.into_iter().zip(hashes) {
+        let file = versions.get(&sha1).and_then(|version| {
+            version
+                .files
+                .iter()
+                .find(|file| file.hashes.get("sha1") == Some(&sha1))
+        });
+        match file {
+            Some(file) => {
+                println!("=> {} (modrinth)", &rel);
+                files.push(PackFile {
+                    path: rel,
+                    hashes: file.hashes.clone(),
+                    env: None,
+                    downloads: vec![file.url.clone()],
+                    file_size: buf.len() as u64,
+                })
+            }
+            None => overrides.push((rel, buf)),
+        }
+    }
+
+    let index = PackIndex {
+        format_version: 1,
+        game: "minecraft".to_string(),
+        version_id: version_id.to_string(),
+        name: inst.name.clone(),
+        summary: None,
+        files,
+        dependencies,
+    };
+
+    let mut zip = ZipWriter::new(File::create(out)?);
+    zip.start_file(MRPACK_INDEX, FileOptions::default())?;
+    zip.write_all(&serde_json::to_vec_pretty(&index)?)?;
+    for (rel, buf) in overrides.iter() {
+        println!("=> overrides/{}", rel);
+        zip.start_file(format!("overrides/{}", rel), FileOptions::default())?;
+        zip.write_all(buf)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
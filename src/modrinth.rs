@@ -0,0 +1,199 @@
+use crate::checksum::verify_hashes;
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const MCL_USER_AGENT: &str = "jlloc/mcl/0.1";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VersionFile {
+    pub hashes: HashMap<String, String>,
+    pub url: String,
+    pub filename: String,
+    #[serde(default)]
+    pub primary: bool,
+    pub size: u64,
+}
+
+impl VersionFile {
+    pub fn verify(&self, buf: &[u8]) -> anyhow::Result<()> {
+        verify_hashes(&self.hashes, buf)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyType {
+    Required,
+    Optional,
+    Incompatible,
+    Embedded,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Dependency {
+    pub version_id: Option<String>,
+    pub project_id: Option<String>,
+    pub file_name: Option<String>,
+    pub dependency_type: DependencyType,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModrinthVersion {
+    pub id: String,
+    pub project_id: String,
+    pub version_number: String,
+    pub files: Vec<VersionFile>,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+}
+
+impl ModrinthVersion {
+    /// The file marked primary, or the first one when none is.
+    pub fn primary_file(&self) -> Option<&VersionFile> {
+        self.files
+            .iter()
+            .find(|f| f.primary)
+            .or_else(|| self.files.first())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Project {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    pub client_side: Option<String>,
+    pub server_side: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchHit {
+    pub project_id: String,
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub downloads: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SearchResponse {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Serialize, Debug)]
+struct HashesQuery<'a> {
+    hashes: &'a [String],
+    algorithm: &'a str,
+}
+
+pub struct Modrinth {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl Modrinth {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Looks up the versions owning files with the given sha1 hashes. Hashes
+    /// unknown to Modrinth are simply absent from the result.
+    pub async fn versions_from_hashes(
+        &self,
+        hashes: &[String],
+    ) -> anyhow::Result<HashMap<String, ModrinthVersion>> {
+        if hashes.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let res = self
+            .client
+            .post(format!("{}/v2/version_files", self.base_url))
+            .header(USER_AGENT, MCL_USER_AGENT)
+            .json(&HashesQuery {
+                hashes,
+                algorithm: "sha1",
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json().await?)
+    }
+
+    /// Searches mods compatible with `game_version` and `loader`.
+    pub async fn search(
+        &self,
+        query: &str,
+        game_version: &str,
+        loader: &str,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        let facets = serde_json::to_string(&[
+            vec!["project_type:mod".to_string()],
+            vec![format!("versions:{}", game_version)],
+            vec![format!("categories:{}", loader)],
+        ])?;
+        let res = self
+            .client
+            .get(format!("{}/v2/search", self.base_url))
+            .header(USER_AGENT, MCL_USER_AGENT)
+            .query(&[("query", query), ("facets", &facets)])
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json::<SearchResponse>().await?.hits)
+    }
+
+    /// Looks up a project by id or slug.
+    pub async fn project(&self, id: &str) -> anyhow::Result<Project> {
+        let res = self
+            .client
+            .get(format!("{}/v2/project/{}", self.base_url, id))
+            .header(USER_AGENT, MCL_USER_AGENT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json().await?)
+    }
+
+    pub async fn version(&self, id: &str) -> anyhow::Result<ModrinthVersion> {
+        let res = self
+            .client
+            .get(format!("{}/v2/version/{}", self.base_url, id))
+            .header(USER_AGENT, MCL_USER_AGENT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json().await?)
+    }
+
+    /// Versions of a project compatible with `game_version` and `loader`,
+    /// newest first.
+    pub async fn project_versions(
+        &self,
+        id: &str,
+        game_version: &str,
+        loader: &str,
+    ) -> anyhow::Result<Vec<ModrinthVersion>> {
+        let res = self
+            .client
+            .get(format!("{}/v2/project/{}/version", self.base_url, id))
+            .header(USER_AGENT, MCL_USER_AGENT)
+            .query(&[
+                ("loaders", serde_json::to_string(&[loader])?),
+                ("game_versions", serde_json::to_string(&[game_version])?),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json().await?)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::checksum::verify_hashes;
+use crate::db::Installation;
+use crate::modrinth::{Dependency, Project};
+use crate::mods::{mod_path, mods_dir, remove_file, ModTarget};
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// File in the installation directory locking the mods mcl installed. A
+/// copy is kept in the game directory to be shared, see [`Lockfile::save`].
+pub const LOCKFILE: &str = "mods.lock";
+
+/// Which side of the game a mod has to be installed on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Both,
+    Client,
+    Server,
+}
+
+impl Side {
+    pub fn of(project: &Project) -> Self {
+        match (
+            project.client_side.as_deref(),
+            project.server_side.as_deref(),
+        ) {
+            (Some("unsupported"), _) => Side::Server,
+            (_, Some("unsupported")) => Side::Client,
+            _ => Side::Both,
+        }
+    }
+}
+
+/// A mod version locked for an installation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LockedMod {
+    pub project_id: String,
+    pub slug: String,
+    pub title: String,
+    pub version_id: String,
+    pub version_number: String,
+    pub filename: String,
+    pub url: String,
+    pub hashes: HashMap<String, String>,
+    pub side: Side,
+    /// Version numbers `mod update` may move to, see [`allows`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+}
+
+impl LockedMod {
+    /// Whether `query` names this mod by project id, slug or title.
+    pub fn matches(&self, query: &str) -> bool {
+        self.project_id == query || self.slug == query || self.title.eq_ignore_ascii_case(query)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Lockfile {
+    pub game_version: Option<String>,
+    pub loader: Option<String>,
+    pub mods: Vec<LockedMod>,
+}
+
+impl Lockfile {
+    pub fn load(inst: &Installation) -> anyhow::Result<Self> {
+        match std::fs::read(inst.path.join(LOCKFILE)) {
+            Ok(buf) => Ok(serde_json::from_slice(&buf)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Reads a lockfile shared from another machine.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let buf = std::fs::read(path)
+            .map_err(|err| anyhow!("could not read {} ({})", path.display(), err))?;
+        Ok(serde_json::from_slice(&buf)?)
+    }
+
+    /// Writes the lockfile to the installation, and a copy next to the mods
+    /// in the game directory, where it can be committed alongside configs
+    /// or handed to `mcl sync --lockfile` elsewhere.
+    pub fn save(&self, inst: &Installation) -> anyhow::Result<()> {
+        let buf = serde_json::to_vec_pretty(self)?;
+        std::fs::write(inst.path.join(LOCKFILE), &buf)?;
+        let game_dir = inst.game_dir();
+        std::fs::create_dir_all(&game_dir)?;
+        std::fs::write(game_dir.join(LOCKFILE), &buf)?;
+        Ok(())
+    }
+
+    pub fn find(&self, query: &str) -> Option<&LockedMod> {
+        self.mods.iter().find(|m| m.matches(query))
+    }
+
+    /// Records the game version and loader the mods were resolved for.
+    pub fn set_target(&mut self, target: &ModTarget) {
+        self.game_version = Some(target.game_version.clone());
+        self.loader = Some(target.loader.clone());
+    }
+
+    /// Fails when the mods were locked for another game version or loader.
+    pub fn check_target(&self, target: &ModTarget) -> anyhow::Result<()> {
+        if let (Some(game_version), Some(loader)) = (&self.game_version, &self.loader) {
+            if game_version != &target.game_version || loader != &target.loader {
+                bail!(
+                    "mods are locked for {} on {}, not {} on {}",
+                    game_version,
+                    loader,
+                    target.game_version,
+                    target.loader
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a version number satisfies a constraint: `=1.2.3` pins a version,
+/// anything else is a prefix (`0.4` allows `0.4.x`).
+pub fn allows(constraint: &str, version_number: &str) -> bool {
+    if let Some(exact) = constraint.strip_prefix('=') {
+        return version_number == exact;
+    }
+    let prefix = constraint.trim_end_matches('*').trim_end_matches('.');
+    version_number == prefix
+        || version_number
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with(['.', '-', '+']))
+}
+
+/// Makes the mods directory match the lockfile: missing or modified jars are
+/// downloaded again and jars the lockfile doesn't know about are removed.
+pub async fn sync(inst: &Installation, lock: &Lockfile) -> anyhow::Result<()> {
+    let dir = mods_dir(inst);
+    let paths = lock
+        .mods
+        .iter()
+        .map(|locked| mod_path(&dir, &locked.filename))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let locked_paths = lock.mods.iter().zip(paths);
+    for (locked, path) in locked_paths.filter(|(m, _)| m.side != Side::Server) {
+        if let Ok(buf) = std::fs::read(&path) {
+            if verify_hashes(&locked.hashes, &buf).is_ok() {
+                continue;
+            }
+        }
+        println!("=> {} {}", &locked.title, &locked.version_number);
+        let buf = reqwest::get(&locked.url)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        verify_hashes(&locked.hashes, &buf)?;
+        std::fs::write(&path, &buf)?;
+    }
+
+    let tracked: HashSet<&str> = lock.mods.iter().map(|m| m.filename.as_str()).collect();
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_file()
+            && name.ends_with(".jar")
+            && !tracked.contains(name.as_str())
+        {
+            println!("=> removing {}", &name);
+            remove_file(&entry.path())?;
+        }
+    }
+    Ok(())
+}
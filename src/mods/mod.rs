@@ -0,0 +1,151 @@
+mod lockfile;
+mod range;
+mod resolve;
+mod scan;
+
+pub use lockfile::*;
+pub use range::*;
+pub use resolve::*;
+pub use scan::*;
+
+use crate::db::Installation;
+use anyhow::{anyhow, ensure};
+use std::path::{Component, Path, PathBuf};
+
+/// The game version and loader mods have to support to run in an
+/// installation.
+#[derive(Debug, Clone)]
+pub struct ModTarget {
+    pub game_version: String,
+    pub loader: String,
+}
+
+impl ModTarget {
+    pub fn of(inst: &Installation) -> anyhow::Result<Self> {
+        let loader = inst
+            .loader
+            .as_ref()
+            .ok_or_else(|| anyhow!("installation {} has no mod loader", inst.name))?;
+        Ok(Self {
+            game_version: inst.game_version()?,
+            loader: loader.to_string(),
+        })
+    }
+}
+
+pub fn mods_dir(inst: &Installation) -> PathBuf {
+    inst.game_dir().join("mods")
+}
+
+/// The path of the jar `filename` in the mods directory `dir`. File names
+/// come from shared lockfiles and the Modrinth API, so anything but a plain
+/// file name is refused.
+pub fn mod_path(dir: &Path, filename: &str) -> anyhow::Result<PathBuf> {
+    let mut components = Path::new(filename).components();
+    ensure!(
+        !filename.contains(['/', '\\'])
+            && matches!(
+                (components.next(), components.next()),
+                (Some(Component::Normal(_)), None)
+            ),
+        "refusing to write outside of the mods directory: {:?}",
+        filename
+    );
+    Ok(dir.join(filename))
+}
+
+/// Downloads the primary file of a resolved version into the mods directory
+/// and locks it, replacing any other version of the project.
+pub async fn install_version(
+    inst: &Installation,
+    lock: &mut Lockfile,
+    resolved: &Resolved,
+) -> anyhow::Result<LockedMod> {
+    let (project, version) = (&resolved.project, &resolved.version);
+    let file = version
+        .primary_file()
+        .ok_or_else(|| anyhow!("version {} has no files", version.id))?;
+
+    println!("=> {} {}", &project.title, &version.version_number);
+    let buf = reqwest::get(&file.url)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    file.verify(&buf)?;
+
+    let dir = mods_dir(inst);
+    let path = mod_path(&dir, &file.filename)?;
+    std::fs::create_dir_all(&dir)?;
+    if let Some(old) = lock.find(&project.id) {
+        if old.filename != file.filename {
+            remove_file(&mod_path(&dir, &old.filename)?)?;
+        }
+    }
+    std::fs::write(path, &buf)?;
+
+    let locked = LockedMod {
+        project_id: project.id.clone(),
+        slug: project.slug.clone(),
+        title: project.title.clone(),
+        version_id: version.id.clone(),
+        version_number: version.version_number.clone(),
+        filename: file.filename.clone(),
+        url: file.url.clone(),
+        hashes: file.hashes.clone(),
+        side: Side::of(project),
+        constraint: resolved.constraint.clone(),
+        dependencies: version.dependencies.clone(),
+    };
+    lock.mods.retain(|m| m.project_id != locked.project_id);
+    lock.mods.push(locked.clone());
+    Ok(locked)
+}
+
+/// Deletes the jar of `query` and drops it from the lockfile.
+pub fn remove(inst: &Installation, lock: &mut Lockfile, query: &str) -> anyhow::Result<LockedMod> {
+    let locked = lock
+        .find(query)
+        .cloned()
+        .ok_or_else(|| anyhow!("no mod {} in {}", query, inst.name))?;
+    remove_file(&mod_path(&mods_dir(inst), &locked.filename)?)?;
+    lock.mods.retain(|m| m.project_id != locked.project_id);
+    Ok(locked)
+}
+
+fn remove_file(path: &Path) -> anyhow::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mod_path_takes_plain_file_names() {
+        let dir = Path::new("/games/mods");
+        assert_eq!(
+            mod_path(dir, "sodium-0.4.4.jar").unwrap(),
+            dir.join("sodium-0.4.4.jar")
+        );
+    }
+
+    #[test]
+    fn mod_path_refuses_escaping_file_names() {
+        let dir = Path::new("/games/mods");
+        for filename in [
+            "../../.bashrc",
+            "..",
+            ".",
+            "",
+            "/etc/passwd",
+            "sub/dir.jar",
+            "..\\evil.jar",
+        ] {
+            assert!(mod_path(dir, filename).is_err(), "{:?}", filename);
+        }
+    }
+}
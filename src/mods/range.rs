@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+
+/// Compares dotted version strings component-wise, numerically where both
+/// components are numbers. Missing components count as zero, so `1.19`
+/// equals `1.19.0`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |v: &str| -> Vec<String> { v.split(['.', '-', '+']).map(String::from).collect() };
+    let (a, b) = (split(a), split(b));
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).map(String::as_str).unwrap_or("0");
+        let y = b.get(i).map(String::as_str).unwrap_or("0");
+        let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Whether `version` satisfies a Fabric/Quilt style predicate: space
+/// separated comparisons (`>=1.19 <1.20`), `~`/`^` ranges, `x` wildcards or
+/// `*`.
+pub fn semver_satisfies(predicate: &str, version: &str) -> bool {
+    predicate.split_whitespace().all(|part| {
+        let (op, v) = match part.find(|c: char| c.is_ascii_alphanumeric() || c == '*') {
+            Some(i) => part.split_at(i),
+            None => return true,
+        };
+        if v == "*" {
+            return true;
+        }
+        if let Some(prefix) = v.strip_suffix(".x").or_else(|| v.strip_suffix(".X")) {
+            return version == prefix || version.starts_with(&format!("{}.", prefix));
+        }
+        let ord = compare_versions(version, v);
+        match op {
+            ">=" => ord != Ordering::Less,
+            "<=" => ord != Ordering::Greater,
+            ">" => ord == Ordering::Greater,
+            "<" => ord == Ordering::Less,
+            "~" => ord != Ordering::Less && same_prefix(version, v, 2),
+            "^" => ord != Ordering::Less && same_prefix(version, v, 1),
+            _ => ord == Ordering::Equal,
+        }
+    })
+}
+
+fn same_prefix(a: &str, b: &str, n: usize) -> bool {
+    let prefix = |v: &str| v.split('.').take(n).collect::<Vec<_>>().join(".");
+    compare_versions(&prefix(a), &prefix(b)) == Ordering::Equal
+}
+
+/// Whether `version` lies in a Maven version range as used by Forge
+/// (`[1.19.2,1.20)`, `[1.19.2]`, several ranges separated by commas). A bare
+/// version is a soft requirement and only sets a minimum.
+pub fn maven_satisfies(range: &str, version: &str) -> bool {
+    let range = range.trim();
+    if range.is_empty() || range == "*" {
+        return true;
+    }
+    if !range.starts_with(['[', '(']) {
+        return compare_versions(version, range) != Ordering::Less;
+    }
+    let mut rest = range;
+    while let Some(start) = rest.find(['[', '(']) {
+        let end = match rest[start..].find([']', ')']) {
+            Some(end) => start + end,
+            None => return false,
+        };
+        let spec = &rest[start..=end];
+        if maven_interval(spec, version) {
+            return true;
+        }
+        rest = &rest[end + 1..];
+    }
+    false
+}
+
+fn maven_interval(spec: &str, version: &str) -> bool {
+    let inclusive_low = spec.starts_with('[');
+    let inclusive_high = spec.ends_with(']');
+    let inner = &spec[1..spec.len() - 1];
+    let (low, high) = match inner.split_once(',') {
+        Some((low, high)) => (low.trim(), high.trim()),
+        None => (inner.trim(), inner.trim()),
+    };
+    let low_ok = low.is_empty()
+        || match compare_versions(version, low) {
+            Ordering::Greater => true,
+            Ordering::Equal => inclusive_low,
+            Ordering::Less => false,
+        };
+    let high_ok = high.is_empty()
+        || match compare_versions(version, high) {
+            Ordering::Less => true,
+            Ordering::Equal => inclusive_high,
+            Ordering::Greater => false,
+        };
+    low_ok && high_ok
+}
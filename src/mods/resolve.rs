@@ -0,0 +1,299 @@
+use crate::modrinth::{DependencyType, Modrinth, ModrinthVersion, Project};
+use crate::mods::{allows, Lockfile, ModTarget};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+/// Mods that could not be installed together, each with an explanation.
+#[derive(Debug)]
+pub struct ConflictError(pub Vec<String>);
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot install a consistent set of mods:")?;
+        for conflict in self.0.iter() {
+            write!(f, "\n  - {}", conflict)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConflictError {}
+
+/// A project to install, as `<project>[@<constraint>]`.
+#[derive(Debug, Clone)]
+pub struct ModRequest {
+    pub project: String,
+    pub constraint: Option<String>,
+}
+
+impl FromStr for ModRequest {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.split_once('@') {
+            Some((project, constraint)) => Self {
+                project: project.to_string(),
+                constraint: Some(constraint.to_string()),
+            },
+            None => Self {
+                project: s.to_string(),
+                constraint: None,
+            },
+        })
+    }
+}
+
+/// A mod version picked by the resolver.
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub project: Project,
+    pub version: ModrinthVersion,
+    /// The mod that pulled this one in, if it was not asked for directly.
+    pub required_by: Option<String>,
+    pub constraint: Option<String>,
+}
+
+impl Resolved {
+    pub fn label(&self) -> String {
+        format!("{} {}", self.project.title, self.version.version_number)
+    }
+
+    /// The label, followed by why the mod is needed.
+    pub fn describe(&self) -> String {
+        match &self.required_by {
+            Some(by) => format!("{} (required by {})", self.label(), by),
+            None => self.label(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub mods: Vec<Resolved>,
+    /// Optional dependencies that were left out.
+    pub suggestions: Vec<String>,
+}
+
+impl Resolution {
+    /// Resolved mods that are not already installed at that version.
+    pub fn changes<'a>(&'a self, lock: &'a Lockfile) -> impl Iterator<Item = &'a Resolved> {
+        self.mods.iter().filter(move |r| {
+            lock.find(&r.project.id)
+                .is_none_or(|entry| entry.version_id != r.version.id)
+        })
+    }
+}
+
+struct Pending {
+    project: Option<String>,
+    version_id: Option<String>,
+    required_by: Option<String>,
+    constraint: Option<String>,
+}
+
+struct Incompatibility {
+    project_id: String,
+    declared_by: String,
+    declarer_id: String,
+    installed: bool,
+}
+
+/// Resolves `requested` projects and their required dependencies, all the
+/// way down, into versions that run on `target` and that neither declare
+/// each other nor the installed mods incompatible. Installed mods satisfy
+/// dependencies as they are; requested ones are moved to their newest
+/// version allowed by the request's constraint, or else the locked one.
+pub async fn resolve(
+    modrinth: &Modrinth,
+    target: &ModTarget,
+    lock: &Lockfile,
+    requested: &[ModRequest],
+) -> anyhow::Result<Resolution> {
+    let mut queue: VecDeque<Pending> = requested
+        .iter()
+        .map(|request| Pending {
+            project: Some(request.project.clone()),
+            version_id: None,
+            required_by: None,
+            constraint: request.constraint.clone().or_else(|| {
+                lock.find(&request.project)
+                    .and_then(|locked| locked.constraint.clone())
+            }),
+        })
+        .collect();
+    let mut resolution = Resolution::default();
+    let mut conflicts = Vec::new();
+    let mut incompatibilities: Vec<Incompatibility> = lock
+        .mods
+        .iter()
+        .flat_map(|entry| {
+            entry.dependencies.iter().filter_map(move |dep| {
+                match (&dep.dependency_type, &dep.project_id) {
+                    (DependencyType::Incompatible, Some(project_id)) => Some(Incompatibility {
+                        project_id: project_id.clone(),
+                        declared_by: format!("{} {}", entry.title, entry.version_number),
+                        declarer_id: entry.project_id.clone(),
+                        installed: true,
+                    }),
+                    _ => None,
+                }
+            })
+        })
+        .collect();
+
+    while let Some(pending) = queue.pop_front() {
+        // dependencies may name an exact version without its project
+        let pinned = match &pending.version_id {
+            Some(id) => Some(modrinth.version(id).await?),
+            None => None,
+        };
+        let project_id = match (&pinned, &pending.project) {
+            (Some(version), _) => version.project_id.clone(),
+            (None, Some(project)) => project.clone(),
+            (None, None) => continue,
+        };
+        let project = modrinth.project(&project_id).await?;
+
+        if let Some(selected) = resolution.mods.iter().find(|r| r.project.id == project.id) {
+            if let Some(version) = pinned.filter(|v| v.id != selected.version.id) {
+                conflicts.push(format!(
+                    "{} needs {} {}, but {} was selected",
+                    pending.required_by.as_deref().unwrap_or("you"),
+                    project.title,
+                    version.version_number,
+                    selected.describe()
+                ));
+            }
+            continue;
+        }
+
+        let version = match pinned {
+            Some(version) => version,
+            None if pending.required_by.is_some() && lock.find(&project.id).is_some() => continue,
+            None => match modrinth
+                .project_versions(&project.id, &target.game_version, &target.loader)
+                .await?
+                .into_iter()
+                .find(|v| {
+                    pending
+                        .constraint
+                        .as_deref()
+                        .is_none_or(|c| allows(c, &v.version_number))
+                }) {
+                Some(version) => version,
+                None => {
+                    let matching = match &pending.constraint {
+                        Some(c) => format!(" matching {}", c),
+                        None => String::new(),
+                    };
+                    conflicts.push(format!(
+                        "{}{} has no version{} for {} on {}",
+                        project.title,
+                        required_by_suffix(&pending.required_by),
+                        matching,
+                        target.game_version,
+                        target.loader
+                    ));
+                    continue;
+                }
+            },
+        };
+
+        let resolved = Resolved {
+            project,
+            version,
+            required_by: pending.required_by,
+            constraint: pending.constraint,
+        };
+        if !resolved
+            .version
+            .game_versions
+            .contains(&target.game_version)
+            || !resolved.version.loaders.contains(&target.loader)
+        {
+            conflicts.push(format!(
+                "{} does not support {} on {}",
+                resolved.describe(),
+                target.game_version,
+                target.loader
+            ));
+        }
+
+        for dep in resolved.version.dependencies.iter() {
+            match dep.dependency_type {
+                DependencyType::Required => queue.push_back(Pending {
+                    project: dep.project_id.clone(),
+                    version_id: dep.version_id.clone(),
+                    required_by: Some(resolved.label()),
+                    constraint: None,
+                }),
+                DependencyType::Optional => {
+                    if let Some(project_id) = &dep.project_id {
+                        let name = match modrinth.project(project_id).await {
+                            Ok(project) => project.title,
+                            Err(_) => project_id.clone(),
+                        };
+                        resolution.suggestions.push(format!(
+                            "{} suggests {}",
+                            resolved.label(),
+                            name
+                        ));
+                    }
+                }
+                DependencyType::Incompatible => {
+                    if let Some(project_id) = &dep.project_id {
+                        incompatibilities.push(Incompatibility {
+                            project_id: project_id.clone(),
+                            declared_by: resolved.label(),
+                            declarer_id: resolved.project.id.clone(),
+                            installed: false,
+                        });
+                    }
+                }
+                // bundled inside the jar, nothing to install
+                DependencyType::Embedded => {}
+            }
+        }
+        resolution.mods.push(resolved);
+    }
+
+    let replaced = |project_id: &str| resolution.mods.iter().any(|r| r.project.id == project_id);
+    for incompatibility in incompatibilities.iter() {
+        // declarations of installed versions that are being replaced no
+        // longer apply
+        if incompatibility.installed && replaced(&incompatibility.declarer_id) {
+            continue;
+        }
+        if let Some(other) = resolution
+            .mods
+            .iter()
+            .find(|r| r.project.id == incompatibility.project_id)
+        {
+            conflicts.push(format!(
+                "{} is incompatible with {}",
+                incompatibility.declared_by,
+                other.describe()
+            ));
+        } else if let Some(entry) = lock.find(&incompatibility.project_id) {
+            if !incompatibility.installed {
+                conflicts.push(format!(
+                    "{} is incompatible with the installed {} {}",
+                    incompatibility.declared_by, entry.title, entry.version_number
+                ));
+            }
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(ConflictError(conflicts).into());
+    }
+    Ok(resolution)
+}
+
+fn required_by_suffix(required_by: &Option<String>) -> String {
+    match required_by {
+        Some(by) => format!(" (required by {})", by),
+        None => String::new(),
+    }
+}
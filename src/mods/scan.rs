@@ -0,0 +1,537 @@
+use crate::loader::LoaderKind;
+use crate::mods::{maven_satisfies, semver_satisfies, Side};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// Mod ids standing for the game, the JVM or a loader rather than a mod.
+const BUILTIN_IDS: [&str; 10] = [
+    "minecraft",
+    "java",
+    "fabricloader",
+    "quilt_loader",
+    "forge",
+    "neoforge",
+    "javafml",
+    "lowcodefml",
+    "mcp",
+    "fml",
+];
+
+/// Where mods bundle other jars: Fabric and Quilt jar-in-jar, and Forge and
+/// NeoForge jarjar.
+const NESTED_JAR_DIRS: [&str; 2] = ["META-INF/jars/", "META-INF/jarjar/"];
+
+/// The metadata file a mod was described by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModFormat {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+    /// `mcmod.info`, from Forge before 1.13.
+    LegacyForge,
+}
+
+impl ModFormat {
+    /// Whether a mod in this format loads on `loader`.
+    pub fn runs_on(&self, loader: &LoaderKind) -> bool {
+        matches!(
+            (self, loader),
+            (ModFormat::Fabric, LoaderKind::Fabric | LoaderKind::Quilt)
+                | (ModFormat::Quilt, LoaderKind::Quilt)
+                | (ModFormat::Forge, LoaderKind::Forge | LoaderKind::NeoForge)
+                | (ModFormat::NeoForge, LoaderKind::NeoForge)
+                | (ModFormat::LegacyForge, LoaderKind::Forge)
+        )
+    }
+
+    fn satisfies(&self, range: &str, version: &str) -> bool {
+        match self {
+            ModFormat::Fabric | ModFormat::Quilt => semver_satisfies(range, version),
+            _ => maven_satisfies(range, version),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeclaredDependency {
+    pub id: String,
+    /// Alternative version ranges, any of which will do. Empty means any
+    /// version.
+    pub versions: Vec<String>,
+    pub required: bool,
+}
+
+/// What a mod jar says about itself.
+#[derive(Debug, Clone)]
+pub struct ModMetadata {
+    pub format: ModFormat,
+    pub id: String,
+    pub name: Option<String>,
+    pub version: String,
+    pub side: Side,
+    pub dependencies: Vec<DeclaredDependency>,
+    /// Other ids this mod stands in for (Fabric and Quilt `provides`).
+    pub provides: Vec<String>,
+}
+
+impl ModMetadata {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
+
+    /// Whether `version` of dependency `id` is acceptable to this mod.
+    /// Mods that don't depend on `id` accept anything.
+    pub fn accepts(&self, id: &str, version: &str) -> bool {
+        self.dependencies
+            .iter()
+            .filter(|dep| dep.id == id)
+            .all(|dep| {
+                dep.versions.is_empty()
+                    || dep
+                        .versions
+                        .iter()
+                        .any(|range| self.format.satisfies(range, version))
+            })
+    }
+}
+
+/// A jar found in a mods directory, with the mods it contains.
+#[derive(Debug)]
+pub struct ScannedJar {
+    pub path: PathBuf,
+    pub mods: Vec<ModMetadata>,
+    /// Mods bundled inside the jar, at any depth.
+    pub bundled: Vec<ModMetadata>,
+}
+
+/// Reads the mod metadata of every jar in `dir`.
+pub fn scan_dir(dir: &Path) -> anyhow::Result<Vec<ScannedJar>> {
+    let mut jars = Vec::new();
+    if !dir.is_dir() {
+        return Ok(jars);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "jar") {
+            continue;
+        }
+        let (mods, bundled) = match inspect(&path) {
+            Ok(inspected) => inspected,
+            Err(err) => {
+                println!("Could not read {:?} ({})", &path, err);
+                Default::default()
+            }
+        };
+        jars.push(ScannedJar {
+            path,
+            mods,
+            bundled,
+        });
+    }
+    jars.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(jars)
+}
+
+/// Reads the mods declared in a jar, and those of the jars nested in it.
+pub fn inspect(jar: &Path) -> anyhow::Result<(Vec<ModMetadata>, Vec<ModMetadata>)> {
+    let mut archive = ZipArchive::new(File::open(jar)?)?;
+    let mods = declared_mods(&mut archive)?;
+    let mut bundled = Vec::new();
+    nested_mods(&mut archive, &mut bundled)?;
+    Ok((mods, bundled))
+}
+
+/// Collects the mods of every jar nested in `archive`, recursively.
+fn nested_mods<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    found: &mut Vec<ModMetadata>,
+) -> anyhow::Result<()> {
+    let nested: Vec<String> = archive
+        .file_names()
+        .filter(|name| {
+            name.ends_with(".jar") && NESTED_JAR_DIRS.iter().any(|dir| name.starts_with(dir))
+        })
+        .map(String::from)
+        .collect();
+    for name in nested {
+        let buf = read_entry(archive, &name)?.unwrap_or_default();
+        // a broken library inside a mod is the loader's problem, not ours
+        let mut inner = match ZipArchive::new(Cursor::new(buf)) {
+            Ok(inner) => inner,
+            Err(_) => continue,
+        };
+        found.extend(declared_mods(&mut inner).unwrap_or_default());
+        nested_mods(&mut inner, found)?;
+    }
+    Ok(())
+}
+
+/// Reads the mods declared in an archive, from whichever metadata files it
+/// has.
+fn declared_mods<R: Read + Seek>(archive: &mut ZipArchive<R>) -> anyhow::Result<Vec<ModMetadata>> {
+    if let Some(buf) = read_entry(archive, "quilt.mod.json")? {
+        return Ok(vec![quilt(serde_json::from_slice(&buf)?)]);
+    }
+    if let Some(buf) = read_entry(archive, "fabric.mod.json")? {
+        return Ok(vec![fabric(serde_json::from_slice(&buf)?)]);
+    }
+    for (file, format) in [
+        ("META-INF/neoforge.mods.toml", ModFormat::NeoForge),
+        ("META-INF/mods.toml", ModFormat::Forge),
+    ] {
+        if let Some(buf) = read_entry(archive, file)? {
+            let jar_version = jar_version(archive)?;
+            return forge(&String::from_utf8_lossy(&buf), format, jar_version);
+        }
+    }
+    if let Some(buf) = read_entry(archive, "mcmod.info")? {
+        return Ok(mcmod_info(serde_json::from_slice(&buf)?));
+    }
+    Ok(Vec::new())
+}
+
+fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    match archive.by_name(name) {
+        Ok(mut file) => {
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            Ok(Some(buf))
+        }
+        Err(zip::result::ZipError::FileNotFound) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// `Implementation-Version` from the jar manifest, which mods.toml refers to
+/// as `${file.jarVersion}`.
+fn jar_version<R: Read + Seek>(archive: &mut ZipArchive<R>) -> anyhow::Result<Option<String>> {
+    let manifest = match archive.by_name("META-INF/MANIFEST.MF") {
+        Ok(manifest) => BufReader::new(manifest),
+        Err(_) => return Ok(None),
+    };
+    for line in manifest.lines() {
+        if let Some(version) = line?.strip_prefix("Implementation-Version:") {
+            return Ok(Some(version.trim().to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// A version or a list of alternatives, as found in JSON metadata.
+fn version_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) if s != "*" => vec![s.clone()],
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Deserialize)]
+struct FabricModJson {
+    id: String,
+    version: String,
+    name: Option<String>,
+    environment: Option<String>,
+    #[serde(default)]
+    depends: HashMap<String, Value>,
+    #[serde(default)]
+    provides: Vec<String>,
+}
+
+fn fabric(json: FabricModJson) -> ModMetadata {
+    ModMetadata {
+        format: ModFormat::Fabric,
+        side: match json.environment.as_deref() {
+            Some("client") => Side::Client,
+            Some("server") => Side::Server,
+            _ => Side::Both,
+        },
+        dependencies: json
+            .depends
+            .iter()
+            .map(|(id, versions)| DeclaredDependency {
+                id: id.clone(),
+                versions: version_list(versions),
+                required: true,
+            })
+            .collect(),
+        provides: json.provides,
+        id: json.id,
+        name: json.name,
+        version: json.version,
+    }
+}
+
+#[derive(Deserialize)]
+struct QuiltModJson {
+    quilt_loader: QuiltLoaderSection,
+    minecraft: Option<QuiltMinecraftSection>,
+}
+
+#[derive(Deserialize)]
+struct QuiltLoaderSection {
+    id: String,
+    version: String,
+    metadata: Option<QuiltMetadata>,
+    #[serde(default)]
+    depends: Vec<Value>,
+    #[serde(default)]
+    provides: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct QuiltMetadata {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct QuiltMinecraftSection {
+    environment: Option<String>,
+}
+
+fn quilt(json: QuiltModJson) -> ModMetadata {
+    let loader = json.quilt_loader;
+    ModMetadata {
+        format: ModFormat::Quilt,
+        side: match json.minecraft.and_then(|m| m.environment).as_deref() {
+            Some("client") => Side::Client,
+            Some("dedicated_server") => Side::Server,
+            _ => Side::Both,
+        },
+        dependencies: loader
+            .depends
+            .iter()
+            .filter_map(|dep| match dep {
+                Value::String(id) => Some(DeclaredDependency {
+                    id: id.clone(),
+                    versions: Vec::new(),
+                    required: true,
+                }),
+                Value::Object(obj) => Some(DeclaredDependency {
+                    id: obj.get("id")?.as_str()?.to_string(),
+                    versions: obj.get("versions").map(version_list).unwrap_or_default(),
+                    required: !obj
+                        .get("optional")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                }),
+                _ => None,
+            })
+            .collect(),
+        provides: loader
+            .provides
+            .iter()
+            .filter_map(|provided| match provided {
+                Value::String(id) => Some(id.clone()),
+                Value::Object(obj) => Some(obj.get("id")?.as_str()?.to_string()),
+                _ => None,
+            })
+            .collect(),
+        id: loader.id,
+        name: loader.metadata.and_then(|m| m.name),
+        version: loader.version,
+    }
+}
+
+#[derive(Deserialize)]
+struct ModsToml {
+    #[serde(default)]
+    mods: Vec<ModsTomlMod>,
+    #[serde(default)]
+    dependencies: HashMap<String, Vec<ModsTomlDependency>>,
+}
+
+#[derive(Deserialize)]
+struct ModsTomlMod {
+    #[serde(rename = "modId")]
+    mod_id: String,
+    version: Option<String>,
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ModsTomlDependency {
+    #[serde(rename = "modId")]
+    mod_id: String,
+    /// Forge's flag; NeoForge uses `type` instead.
+    mandatory: Option<bool>,
+    #[serde(rename = "type")]
+    dependency_type: Option<String>,
+    #[serde(rename = "versionRange")]
+    version_range: Option<String>,
+}
+
+fn forge(
+    toml: &str,
+    format: ModFormat,
+    jar_version: Option<String>,
+) -> anyhow::Result<Vec<ModMetadata>> {
+    let mut file: ModsToml = toml::from_str(toml)?;
+    Ok(file
+        .mods
+        .into_iter()
+        .map(|m| {
+            let version = match m.version.as_deref() {
+                Some("${file.jarVersion}") | None => jar_version.clone().unwrap_or_default(),
+                Some(v) => v.to_string(),
+            };
+            ModMetadata {
+                format,
+                side: Side::Both,
+                dependencies: file
+                    .dependencies
+                    .remove(&m.mod_id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|dep| DeclaredDependency {
+                        required: match dep.dependency_type.as_deref() {
+                            Some(t) => t.eq_ignore_ascii_case("required"),
+                            None => dep.mandatory.unwrap_or(true),
+                        },
+                        versions: dep.version_range.into_iter().collect(),
+                        id: dep.mod_id,
+                    })
+                    .collect(),
+                provides: Vec::new(),
+                id: m.mod_id,
+                name: m.display_name,
+                version,
+            }
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum McModInfo {
+    List(Vec<McModInfoEntry>),
+    V2 {
+        #[serde(rename = "modList")]
+        mod_list: Vec<McModInfoEntry>,
+    },
+}
+
+#[derive(Deserialize)]
+struct McModInfoEntry {
+    modid: String,
+    name: Option<String>,
+    #[serde(default)]
+    version: String,
+    mcversion: Option<String>,
+    #[serde(rename = "requiredMods", default)]
+    required_mods: Vec<String>,
+}
+
+fn mcmod_info(info: McModInfo) -> Vec<ModMetadata> {
+    let entries = match info {
+        McModInfo::List(entries) => entries,
+        McModInfo::V2 { mod_list } => mod_list,
+    };
+    entries
+        .into_iter()
+        .map(|entry| {
+            let mut dependencies: Vec<DeclaredDependency> = entry
+                .required_mods
+                .iter()
+                .map(|spec| {
+                    // `modid@[range]`
+                    let (id, range) = spec.split_once('@').unwrap_or((spec, ""));
+                    DeclaredDependency {
+                        id: id.to_string(),
+                        versions: Some(range.to_string())
+                            .filter(|r| !r.is_empty())
+                            .into_iter()
+                            .collect(),
+                        required: true,
+                    }
+                })
+                .collect();
+            if let Some(mcversion) = entry.mcversion.filter(|v| !v.is_empty()) {
+                dependencies.push(DeclaredDependency {
+                    id: "minecraft".to_string(),
+                    versions: vec![format!("[{}]", mcversion)],
+                    required: true,
+                });
+            }
+            ModMetadata {
+                format: ModFormat::LegacyForge,
+                id: entry.modid,
+                name: entry.name,
+                version: entry.version,
+                side: Side::Both,
+                dependencies,
+                provides: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Problems with the scanned mods for `game_version` on `loader`: mods for
+/// another loader, game version or side, and required mods that are missing.
+pub fn check(jars: &[ScannedJar], game_version: &str, loader: &LoaderKind) -> Vec<String> {
+    // bundled mods and provided ids count as installed, but only the jars'
+    // own mods are checked: the loader vets what they bundle. Top-level mods
+    // win over bundled copies of themselves.
+    let mut present: HashMap<&str, &ModMetadata> = HashMap::new();
+    let top_level = jars.iter().flat_map(|jar| jar.mods.iter());
+    let bundled = jars.iter().flat_map(|jar| jar.bundled.iter());
+    for m in top_level.chain(bundled) {
+        for id in std::iter::once(&m.id).chain(m.provides.iter()) {
+            present.entry(id.as_str()).or_insert(m);
+        }
+    }
+    let mut problems = Vec::new();
+    for jar in jars.iter() {
+        let file = jar
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for m in jar.mods.iter() {
+            let label = format!("{} {} ({})", m.id, m.version, file);
+            if !m.format.runs_on(loader) {
+                problems.push(format!("{} is not a {} mod", label, loader));
+                continue;
+            }
+            if m.side == Side::Server {
+                problems.push(format!("{} only runs on servers", label));
+            }
+            if !m.accepts("minecraft", game_version) {
+                problems.push(format!(
+                    "{} does not support Minecraft {}",
+                    label, game_version
+                ));
+            }
+            for dep in m.dependencies.iter().filter(|d| d.required) {
+                if BUILTIN_IDS.contains(&dep.id.to_lowercase().as_str()) {
+                    continue;
+                }
+                match present.get(dep.id.as_str()) {
+                    None => problems.push(format!("{} requires {}", label, dep.id)),
+                    Some(other) if !m.accepts(&dep.id, &other.version) => problems.push(format!(
+                        "{} requires {} {}, found {}",
+                        label,
+                        dep.id,
+                        dep.versions.join(" or "),
+                        other.version
+                    )),
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    problems
+}